@@ -0,0 +1,78 @@
+//! Compares the streaming JSON path against the `serde_json::Value` one.
+//!
+//! Run with `cargo bench --bench json_stream`. Set `DEVKIT_BENCH_MB` to
+//! change the size of the generated document (default 32 MB).
+
+use devkit::commands::json::stream;
+use serde_json::Value;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Build a document shaped like a typical API dump: an array of records
+fn generate(target_bytes: usize) -> String {
+    let mut doc = String::with_capacity(target_bytes + 1024);
+    doc.push_str("[\n");
+    let mut i = 0u64;
+    while doc.len() < target_bytes {
+        if i > 0 {
+            doc.push_str(",\n");
+        }
+        doc.push_str(&format!(
+            r#"  {{ "id": {i}, "name": "user-{i}", "email": "user{i}@example.com",
+    "score": {}.{}, "active": {}, "tags": ["a", "b\n", "é"],
+    "address": {{ "city": "Springfield", "zip": "{:05}", "geo": [12.5, -70.25] }},
+    "notes": null }}"#,
+            i % 1000,
+            i % 97,
+            i & 1 == 0,
+            i % 100_000
+        ));
+        i += 1;
+    }
+    doc.push_str("\n]\n");
+    doc
+}
+
+fn time<F: FnMut()>(label: &str, bytes: usize, mut f: F) -> Duration {
+    // Warm up once, then keep the best of three runs
+    f();
+    let best = (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+    let mb_per_sec = bytes as f64 / 1_048_576.0 / best.as_secs_f64();
+    println!("{:<28} {:>10.2?} {:>10.1} MB/s", label, best, mb_per_sec);
+    best
+}
+
+fn main() {
+    let mb: usize = std::env::var("DEVKIT_BENCH_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(32);
+    let doc = generate(mb * 1_048_576);
+    let bytes = doc.len();
+    println!("document: {:.1} MB\n", bytes as f64 / 1_048_576.0);
+
+    time("minify  (Value)", bytes, || {
+        let value: Value = serde_json::from_str(&doc).unwrap();
+        black_box(serde_json::to_string(&value).unwrap());
+    });
+    time("minify  (stream)", bytes, || {
+        let mut out = Vec::with_capacity(bytes);
+        stream::minify(doc.as_bytes(), &mut out).unwrap();
+        black_box(out);
+    });
+
+    time("validate (Value)", bytes, || {
+        let value: Value = serde_json::from_str(&doc).unwrap();
+        black_box(value);
+    });
+    time("validate (stream)", bytes, || {
+        black_box(stream::scan(doc.as_bytes()).unwrap());
+    });
+}
@@ -0,0 +1,376 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::path::Path;
+
+use super::input::{file_hint, read_input, Source};
+
+/// Document formats understood by `convert`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// Parse a format name as given on the command line
+    pub fn parse(name: &str) -> Result<Format> {
+        match name.to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            _ => bail!("Unknown format: {}. Use json, yaml, or toml.", name),
+        }
+    }
+
+    fn from_extension(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_str()?;
+        Format::parse(ext).ok()
+    }
+
+    /// Guess the format of a document from its content
+    fn detect(content: &str) -> Format {
+        if serde_json::from_str::<Value>(content).is_ok() {
+            Format::Json
+        } else if content.parse::<toml::Table>().is_ok() {
+            // Checked before YAML: `key = "value"` is also a valid YAML scalar
+            Format::Toml
+        } else {
+            Format::Yaml
+        }
+    }
+}
+
+/// Convert a document between JSON, YAML and TOML
+///
+/// When `from` is not given the format is taken from the file extension, or
+/// detected from the content for literal input. A YAML stream with several
+/// `---` documents becomes a JSON array, or stays a stream as YAML.
+pub fn convert(input: Source<'_>, from: Option<&str>, to: &str) -> Result<String> {
+    let content = read_input(input)?;
+    let to = Format::parse(to)?;
+    let from = match from {
+        Some(name) => Format::parse(name)?,
+        None => match input {
+            Source::File(path) => Format::from_extension(path),
+            _ => None,
+        }
+        .unwrap_or_else(|| Format::detect(&content)),
+    };
+
+    let mut documents = parse(&content, from)?;
+    if documents.len() > 1 {
+        return emit_stream(documents, to);
+    }
+    let value = documents.pop().unwrap_or(Value::Null);
+    // A bare path parses as a YAML string, so catch the likely mistake here
+    let hint = file_hint(input);
+    if value.is_string() && !hint.is_empty() {
+        bail!("Input is a plain string{}", hint);
+    }
+    emit(&value, to)
+}
+
+/// Parse a document into JSON values, one for each document of a YAML stream
+pub fn parse(content: &str, format: Format) -> Result<Vec<Value>> {
+    match format {
+        Format::Json => Ok(vec![
+            serde_json::from_str(content).context("Invalid JSON syntax")?
+        ]),
+        Format::Yaml => {
+            let documents = serde_yaml::Deserializer::from_str(content)
+                .map(serde_yaml::Value::deserialize)
+                .collect::<Result<Vec<_>, _>>()
+                .context("Invalid YAML syntax")?;
+            let stream = documents.len() > 1;
+            documents
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut yaml)| {
+                    let path = if stream {
+                        format!("$[{}]", i)
+                    } else {
+                        "$".into()
+                    };
+                    // `<<: *anchor` merge keys
+                    yaml.apply_merge()
+                        .with_context(|| format!("Invalid YAML merge key in {}", path))?;
+                    yaml_to_json(yaml, &path)
+                })
+                .collect()
+        }
+        Format::Toml => {
+            let table: toml::Table = content.parse().context("Invalid TOML syntax")?;
+            Ok(vec![toml_to_json(toml::Value::Table(table), "$")?])
+        }
+    }
+}
+
+/// Serialize the documents of a YAML stream: a JSON array, or YAML documents
+/// separated by `---`
+fn emit_stream(documents: Vec<Value>, format: Format) -> Result<String> {
+    match format {
+        Format::Json => emit(&Value::Array(documents), format),
+        Format::Yaml => {
+            let documents = documents
+                .iter()
+                .map(|value| emit(value, format))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(documents.join("\n---\n"))
+        }
+        Format::Toml => bail!(
+            "Input has {} YAML documents but TOML holds only one; convert to json or yaml instead",
+            documents.len()
+        ),
+    }
+}
+
+/// Serialize a JSON value in the given format
+pub fn emit(value: &Value, format: Format) -> Result<String> {
+    match format {
+        Format::Json => serde_json::to_string_pretty(value).context("Failed to format JSON"),
+        Format::Yaml => {
+            let yaml = serde_yaml::to_string(value).context("Failed to write YAML")?;
+            Ok(yaml.trim_end().to_string())
+        }
+        Format::Toml => {
+            let table = match json_to_toml(value, "$")? {
+                toml::Value::Table(table) => table,
+                _ => bail!(
+                    "TOML documents must be a table at the top level, not {}",
+                    kind(value)
+                ),
+            };
+            let toml = toml::to_string_pretty(&table).context("Failed to write TOML")?;
+            Ok(toml.trim_end().to_string())
+        }
+    }
+}
+
+fn yaml_to_json(value: serde_yaml::Value, path: &str) -> Result<Value> {
+    use serde_yaml::Value as Yaml;
+
+    Ok(match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                let f = n.as_f64().unwrap_or(f64::NAN);
+                Value::Number(
+                    Number::from_f64(f).with_context(|| {
+                        format!("{} at {} cannot be represented in JSON", n, path)
+                    })?,
+                )
+            }
+        }
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| yaml_to_json(item, &format!("{}[{}]", path, i)))
+                .collect::<Result<_>>()?,
+        ),
+        Yaml::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (key, item) in mapping {
+                let key = match key {
+                    Yaml::String(s) => s,
+                    other => bail!(
+                        "Non-string key {} at {} cannot be represented in JSON",
+                        serde_yaml::to_string(&other).unwrap_or_default().trim_end(),
+                        path
+                    ),
+                };
+                let item = yaml_to_json(item, &format!("{}.{}", path, key))?;
+                map.insert(key, item);
+            }
+            Value::Object(map)
+        }
+        Yaml::Tagged(tagged) => bail!(
+            "YAML tag {} at {} cannot be represented in JSON",
+            tagged.tag,
+            path
+        ),
+    })
+}
+
+fn toml_to_json(value: toml::Value, path: &str) -> Result<Value> {
+    use toml::Value as Toml;
+
+    Ok(match value {
+        Toml::String(s) => Value::String(s),
+        Toml::Integer(i) => Value::from(i),
+        Toml::Float(f) => Value::Number(
+            Number::from_f64(f)
+                .with_context(|| format!("{} at {} cannot be represented in JSON", f, path))?,
+        ),
+        Toml::Boolean(b) => Value::Bool(b),
+        // JSON has no date type; keep the RFC 3339 text
+        Toml::Datetime(dt) => Value::String(dt.to_string()),
+        Toml::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| toml_to_json(item, &format!("{}[{}]", path, i)))
+                .collect::<Result<_>>()?,
+        ),
+        Toml::Table(table) => {
+            let mut map = Map::new();
+            for (key, item) in table {
+                let item = toml_to_json(item, &format!("{}.{}", path, key))?;
+                map.insert(key, item);
+            }
+            Value::Object(map)
+        }
+    })
+}
+
+fn json_to_toml(value: &Value, path: &str) -> Result<toml::Value> {
+    use toml::Value as Toml;
+
+    Ok(match value {
+        Value::Null => bail!("TOML has no null value (found null at {})", path),
+        Value::Bool(b) => Toml::Boolean(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Toml::Integer(i)
+            } else if n.is_u64() {
+                bail!(
+                    "{} at {} is too large for a TOML integer (max {})",
+                    n,
+                    path,
+                    i64::MAX
+                )
+            } else {
+                Toml::Float(n.as_f64().unwrap_or_default())
+            }
+        }
+        Value::String(s) => Toml::String(s.clone()),
+        Value::Array(items) => Toml::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| json_to_toml(item, &format!("{}[{}]", path, i)))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => {
+            let mut table = toml::Table::new();
+            for (key, item) in map {
+                table.insert(
+                    key.clone(),
+                    json_to_toml(item, &format!("{}.{}", path, key))?,
+                );
+            }
+            Toml::Table(table)
+        }
+    })
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_to_json() {
+        let yaml = "name: web\nspec:\n  replicas: 3\n  ports: [80, 443]\n";
+        let result = convert(Source::Literal(yaml), Some("yaml"), "json").unwrap();
+        let value: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(value["spec"]["replicas"], 3);
+        assert_eq!(value["spec"]["ports"][1], 443);
+        // Key order follows the source document
+        assert!(result.find("name").unwrap() < result.find("spec").unwrap());
+    }
+
+    #[test]
+    fn test_yaml_stream() {
+        let yaml = "kind: Service\nname: web\n---\nkind: Deployment\nreplicas: 3\n";
+        let json = convert(Source::Literal(yaml), Some("yaml"), "json").unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["kind"], "Service");
+        assert_eq!(value[1]["replicas"], 3);
+        assert_eq!(
+            convert(Source::Literal(yaml), Some("yaml"), "yaml").unwrap(),
+            "kind: Service\nname: web\n---\nkind: Deployment\nreplicas: 3"
+        );
+        let err = convert(Source::Literal(yaml), Some("yaml"), "toml").unwrap_err();
+        assert!(err.to_string().contains("2 YAML documents"));
+        // A leading `---` still marks a single document
+        let single = convert(Source::Literal("---\na: 1\n"), Some("yaml"), "json").unwrap();
+        assert!(single.starts_with('{'));
+    }
+
+    #[test]
+    fn test_yaml_merge_keys() {
+        let yaml = "base: &base\n  image: nginx\n  port: 80\nweb:\n  <<: *base\n  port: 8080\n";
+        let value: Value =
+            serde_json::from_str(&convert(Source::Literal(yaml), Some("yaml"), "json").unwrap())
+                .unwrap();
+        assert_eq!(value["web"]["image"], "nginx");
+        // Keys in the mapping itself win over merged ones
+        assert_eq!(value["web"]["port"], 8080);
+        assert!(value["web"].get("<<").is_none());
+    }
+
+    #[test]
+    fn test_json_to_toml() {
+        let json = r#"{"title": "x", "owner": {"name": "ann"}, "ports": [1, 2]}"#;
+        let result = convert(Source::Literal(json), None, "toml").unwrap();
+        assert!(result.contains("title = \"x\""));
+        assert!(result.contains("[owner]"));
+    }
+
+    #[test]
+    fn test_toml_roundtrip_through_yaml() {
+        let toml = "[package]\nname = \"devkit\"\nversion = \"1.0.0\"\n";
+        let yaml = convert(Source::Literal(toml), None, "yaml").unwrap();
+        assert!(yaml.contains("package:"));
+        let back = convert(Source::Literal(&yaml), Some("yaml"), "toml").unwrap();
+        assert!(back.contains("name = \"devkit\""));
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect(r#"{"a": 1}"#), Format::Json);
+        assert_eq!(Format::detect("a = 1"), Format::Toml);
+        assert_eq!(Format::detect("a: 1"), Format::Yaml);
+    }
+
+    #[test]
+    fn test_toml_null_error() {
+        let err = convert(Source::Literal(r#"{"a": {"b": null}}"#), None, "toml").unwrap_err();
+        assert!(err.to_string().contains("$.a.b"));
+    }
+
+    #[test]
+    fn test_toml_root_must_be_table() {
+        assert!(convert(Source::Literal("[1, 2]"), None, "toml").is_err());
+    }
+
+    #[test]
+    fn test_non_string_key_error() {
+        let err = convert(Source::Literal("1: one\n"), Some("yaml"), "json").unwrap_err();
+        assert!(err.to_string().contains("Non-string key"));
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        assert!(convert(Source::Literal("{}"), Some("xml"), "json").is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+use super::input::{read_input, Source};
+use super::json::parse_input;
+use super::json::stats::member_path;
+
+/// How nested arrays are laid out when flattening to CSV columns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrayMode {
+    /// One column per element: `tags.0`, `tags.1`, ...
+    Index,
+    /// Scalars joined with `;` in a single column
+    Join,
+    /// The array embedded as JSON text in a single column
+    Json,
+}
+
+impl ArrayMode {
+    pub fn parse(name: &str) -> Result<ArrayMode> {
+        match name.to_lowercase().as_str() {
+            "index" => Ok(ArrayMode::Index),
+            "join" => Ok(ArrayMode::Join),
+            "json" => Ok(ArrayMode::Json),
+            _ => bail!("Unknown array mode: {}. Use index, join, or json.", name),
+        }
+    }
+}
+
+/// Convert JSON (an array of objects, or a single object) to CSV
+///
+/// Nested objects become dot-notation columns joined with `separator`; the
+/// header is the union of every row's columns in first-seen order.
+pub fn from_json(input: Source<'_>, separator: &str, arrays: &str) -> Result<String> {
+    let value = parse_input(input)?;
+    let mode = ArrayMode::parse(arrays)?;
+
+    let records = match value {
+        Value::Array(items) => items,
+        other => vec![other],
+    };
+
+    let mut headers: Vec<String> = Vec::new();
+    let mut rows: Vec<Map<String, Value>> = Vec::with_capacity(records.len());
+    for (i, record) in records.iter().enumerate() {
+        let mut row = Row::default();
+        let path = format!("$[{}]", i);
+        match record {
+            Value::Object(_) => flatten(record, "", &path, separator, mode, &mut row)?,
+            // Bare scalars get a single `value` column
+            other => flatten(other, "value", &path, separator, mode, &mut row)?,
+        }
+        for key in row.cells.keys() {
+            if !headers.contains(key) {
+                headers.push(key.clone());
+            }
+        }
+        rows.push(row.cells);
+    }
+    // No columns at all (`[]`, `[{}]`): no header either
+    if headers.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer.write_record(&headers)?;
+    for row in &rows {
+        writer.write_record(
+            headers
+                .iter()
+                .map(|h| row.get(h).map(cell).unwrap_or_default()),
+        )?;
+    }
+
+    let bytes = writer.into_inner().context("Failed to write CSV")?;
+    let csv = String::from_utf8(bytes).context("Failed to write CSV")?;
+    Ok(csv.trim_end().to_string())
+}
+
+/// The cells of one record, with the JSON path each column came from
+#[derive(Default)]
+struct Row {
+    cells: Map<String, Value>,
+    paths: HashMap<String, String>,
+}
+
+impl Row {
+    fn insert(&mut self, column: &str, path: &str, value: Value) -> Result<()> {
+        if let Some(earlier) = self.paths.get(column) {
+            bail!(
+                "Column '{}' would hold both {} and {}; try another --separator",
+                column,
+                earlier,
+                path
+            );
+        }
+        self.paths.insert(column.to_string(), path.to_string());
+        self.cells.insert(column.to_string(), value);
+        Ok(())
+    }
+}
+
+fn flatten(
+    value: &Value,
+    prefix: &str,
+    path: &str,
+    separator: &str,
+    mode: ArrayMode,
+    row: &mut Row,
+) -> Result<()> {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}{}{}", prefix, separator, key)
+        }
+    };
+
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map {
+                let item_path = format!("{}{}", path, member_path(key));
+                flatten(item, &join(key), &item_path, separator, mode, row)?;
+            }
+        }
+        Value::Array(items) if !items.is_empty() => match mode {
+            ArrayMode::Index => {
+                for (i, item) in items.iter().enumerate() {
+                    let item_path = format!("{}[{}]", path, i);
+                    flatten(
+                        item,
+                        &join(&i.to_string()),
+                        &item_path,
+                        separator,
+                        mode,
+                        row,
+                    )?;
+                }
+            }
+            ArrayMode::Join if items.iter().all(|i| !i.is_object() && !i.is_array()) => {
+                let joined = items.iter().map(cell).collect::<Vec<_>>().join(";");
+                row.insert(prefix, path, Value::String(joined))?;
+            }
+            ArrayMode::Join | ArrayMode::Json => {
+                row.insert(prefix, path, Value::String(value.to_string()))?;
+            }
+        },
+        other => row.insert(prefix, path, other.clone())?,
+    }
+    Ok(())
+}
+
+/// Text of a single CSV cell
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Convert CSV with a header row to a JSON array of objects
+///
+/// With `infer` enabled, empty cells become `null` and cells that look like
+/// numbers or booleans are converted; anything else stays a string.
+pub fn to_json(input: Source<'_>, delimiter: char, infer: bool) -> Result<String> {
+    let content = read_input(input)?;
+    if !delimiter.is_ascii() {
+        bail!("Delimiter must be a single ASCII character");
+    }
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .from_reader(content.as_bytes());
+    let headers = reader.headers().context("Invalid CSV header")?.clone();
+    for (i, header) in headers.iter().enumerate() {
+        if headers.iter().take(i).any(|h| h == header) {
+            bail!("Duplicate CSV column '{}' at position {}", header, i + 1);
+        }
+    }
+
+    let mut records = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Invalid CSV at record {}", i + 1))?;
+        let mut object = Map::new();
+        for (header, field) in headers.iter().zip(record.iter()) {
+            let value = if infer {
+                infer_value(field)
+            } else {
+                Value::String(field.to_string())
+            };
+            object.insert(header.to_string(), value);
+        }
+        records.push(Value::Object(object));
+    }
+
+    serde_json::to_string_pretty(&Value::Array(records)).context("Failed to format JSON")
+}
+
+fn infer_value(field: &str) -> Value {
+    match field {
+        "" | "null" => return Value::Null,
+        "true" => return Value::Bool(true),
+        "false" => return Value::Bool(false),
+        _ => {}
+    }
+
+    // Leading zeros usually mean an identifier (zip codes, account numbers)
+    let digits = field.strip_prefix('-').unwrap_or(field);
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+        return Value::String(field.to_string());
+    }
+
+    if let Ok(i) = field.parse::<i64>() {
+        return Value::from(i);
+    }
+    if let Ok(u) = field.parse::<u64>() {
+        return Value::from(u);
+    }
+    let looks_numeric = field
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+    if looks_numeric {
+        // Only when no digits are lost, so long IDs stay intact as strings
+        if let Some(n) = field
+            .parse::<f64>()
+            .ok()
+            .filter(|f| significant_digits(&format!("{:e}", f)) == significant_digits(field))
+            .and_then(Number::from_f64)
+        {
+            return Value::Number(n);
+        }
+    }
+    Value::String(field.to_string())
+}
+
+/// The digits of a number without sign, point, exponent or padding zeros:
+/// `-0.0150e3` -> `15`
+fn significant_digits(number: &str) -> String {
+    let mantissa = number.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    digits.trim_matches('0').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_nested() {
+        let json = r#"[{"id": 1, "user": {"name": "ann", "geo": {"lat": 1.5}}},
+                      {"id": 2, "user": {"name": "bob, jr"}, "extra": true}]"#;
+        let csv = from_json(Source::Literal(json), ".", "index").unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "id,user.name,user.geo.lat,extra");
+        assert_eq!(lines[1], "1,ann,1.5,");
+        assert_eq!(lines[2], "2,\"bob, jr\",,true");
+    }
+
+    #[test]
+    fn test_array_modes() {
+        let json = r#"{"tags": ["a", "b"]}"#;
+        assert_eq!(
+            from_json(Source::Literal(json), "_", "index").unwrap(),
+            "tags_0,tags_1\na,b"
+        );
+        assert_eq!(
+            from_json(Source::Literal(json), ".", "join").unwrap(),
+            "tags\na;b"
+        );
+        assert_eq!(
+            from_json(Source::Literal(json), ".", "json").unwrap(),
+            "tags\n\"[\"\"a\"\",\"\"b\"\"]\""
+        );
+    }
+
+    #[test]
+    fn test_to_json_infers_types() {
+        let csv = "id,name,active,score,zip,note\n1,ann,true,2.5,02134,\n";
+        let value: Value =
+            serde_json::from_str(&to_json(Source::Literal(csv), ',', true).unwrap()).unwrap();
+        let row = &value[0];
+        assert_eq!(row["id"], 1);
+        assert_eq!(row["active"], true);
+        assert_eq!(row["score"], 2.5);
+        assert_eq!(row["zip"], "02134");
+        assert_eq!(row["note"], Value::Null);
+    }
+
+    #[test]
+    fn test_to_json_keeps_large_numbers_exact() {
+        let csv = "a,b,c,d,e\n18446744073709551615,18446744073709551616,1.50,1e3,0.1000000000000000000001\n";
+        let value: Value =
+            serde_json::from_str(&to_json(Source::Literal(csv), ',', true).unwrap()).unwrap();
+        let row = &value[0];
+        assert_eq!(row["a"], u64::MAX);
+        assert_eq!(row["b"], "18446744073709551616");
+        assert_eq!(row["c"], 1.5);
+        assert_eq!(row["d"], 1000.0);
+        assert_eq!(row["e"], "0.1000000000000000000001");
+    }
+
+    #[test]
+    fn test_to_json_without_inference() {
+        let value: Value =
+            serde_json::from_str(&to_json(Source::Literal("a;b\n1;x"), ';', false).unwrap())
+                .unwrap();
+        assert_eq!(value[0]["a"], "1");
+        assert_eq!(value[0]["b"], "x");
+    }
+
+    #[test]
+    fn test_column_collisions() {
+        let err = from_json(Source::Literal(r#"{"a.b":1,"a":{"b":2}}"#), ".", "index").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Column 'a.b' would hold both $[0]["a.b"] and $[0].a.b; try another --separator"#
+        );
+        assert_eq!(
+            from_json(Source::Literal(r#"{"a.b":1,"a":{"b":2}}"#), "/", "index").unwrap(),
+            "a.b,a/b\n1,2"
+        );
+        assert_eq!(from_json(Source::Literal("[]"), ".", "index").unwrap(), "");
+    }
+
+    #[test]
+    fn test_to_json_rejects_duplicate_headers() {
+        let err = to_json(Source::Literal("a,b,a\n1,2,3"), ',', true).unwrap_err();
+        assert_eq!(err.to_string(), "Duplicate CSV column 'a' at position 3");
+    }
+
+    #[test]
+    fn test_unknown_array_mode() {
+        assert!(from_json(Source::Literal("[]"), ".", "explode").is_err());
+    }
+}
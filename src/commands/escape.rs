@@ -0,0 +1,507 @@
+use anyhow::{bail, Context, Result};
+
+use super::input::{read_input, Source};
+use super::url;
+
+/// Where an escaped string is going to be embedded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Json,
+    Shell,
+    Regex,
+    Html,
+    Xml,
+    Csv,
+    C,
+    Url,
+}
+
+impl Target {
+    pub fn parse(name: &str) -> Result<Target> {
+        match name.to_lowercase().as_str() {
+            "json" => Ok(Target::Json),
+            "shell" | "sh" => Ok(Target::Shell),
+            "regex" => Ok(Target::Regex),
+            "html" => Ok(Target::Html),
+            "xml" => Ok(Target::Xml),
+            "csv" => Ok(Target::Csv),
+            "c" => Ok(Target::C),
+            "url" => Ok(Target::Url),
+            _ => bail!(
+                "Unknown target: {}. Use json, shell, regex, html, xml, csv, c, or url.",
+                name
+            ),
+        }
+    }
+}
+
+/// Escape a string for embedding in the given context
+///
+/// JSON and C produce a complete quoted literal; shell and CSV add quotes
+/// only when needed.
+pub fn escape(input: Source<'_>, target: &str) -> Result<String> {
+    let text = read_input(input)?;
+
+    Ok(match Target::parse(target)? {
+        Target::Json => serde_json::to_string(&text).context("Failed to escape JSON")?,
+        Target::Shell => escape_shell(&text),
+        Target::Regex => escape_regex(&text),
+        Target::Html => escape_markup(&text, "&#39;"),
+        Target::Xml => escape_markup(&text, "&apos;"),
+        Target::Csv => escape_csv(&text),
+        Target::C => escape_c(&text),
+        Target::Url => url::encode(&text, url::Component::Value)?,
+    })
+}
+
+/// Reverse `escape` for the given context
+pub fn unescape(input: Source<'_>, target: &str) -> Result<String> {
+    let text = read_input(input)?;
+
+    match Target::parse(target)? {
+        Target::Json => unescape_json(&text),
+        Target::Shell => unescape_shell(&text),
+        Target::Regex => unescape_regex(&text),
+        Target::Html => Ok(unescape_markup(&text, true)),
+        Target::Xml => Ok(unescape_markup(&text, false)),
+        Target::Csv => unescape_csv(&text),
+        Target::C => unescape_c(strip_quotes(&text, '"')),
+        Target::Url => url::decode(&text, false, false),
+    }
+}
+
+fn strip_quotes(text: &str, quote: char) -> &str {
+    text.strip_prefix(quote)
+        .and_then(|t| t.strip_suffix(quote))
+        .unwrap_or(text)
+}
+
+fn escape_shell(text: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+    if !text.is_empty() && text.chars().all(safe) {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+/// Undo POSIX shell quoting: '...', "...", $'...' and backslash escapes
+fn unescape_shell(text: &str) -> Result<String> {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some(c) => out.push(c),
+                    None => bail!("Unterminated single quote"),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c @ ('$' | '`' | '"' | '\\')) => out.push(c),
+                        Some('\n') => {}
+                        Some(c) => {
+                            out.push('\\');
+                            out.push(c);
+                        }
+                        None => bail!("Unterminated double quote"),
+                    },
+                    Some(c) => out.push(c),
+                    None => bail!("Unterminated double quote"),
+                }
+            },
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                let mut body = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => {
+                            body.push('\\');
+                            body.extend(chars.next());
+                        }
+                        Some(c) => body.push(c),
+                        None => bail!("Unterminated $'...' quote"),
+                    }
+                }
+                out.push_str(&unescape_c(&body)?);
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => out.push(c),
+                None => bail!("Trailing backslash"),
+            },
+            c => out.push(c),
+        }
+    }
+
+    Ok(out)
+}
+
+/// Metacharacters every common flavour (PCRE, JS, POSIX ERE, Rust) accepts escaped
+const REGEX_META: &str = r"\.+*?()|[]{}^$";
+
+fn escape_regex(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if REGEX_META.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn unescape_regex(text: &str) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(c) if c.is_alphanumeric() => {
+                bail!(
+                    "\\{} is a regex class or assertion, not an escaped literal",
+                    c
+                )
+            }
+            Some(c) => out.push(c),
+            None => bail!("Trailing backslash"),
+        }
+    }
+    Ok(out)
+}
+
+fn escape_markup(text: &str, apostrophe: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str(apostrophe),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Decode character references; HTML also gets a handful of common named
+/// entities. Anything unrecognised is left untouched.
+fn unescape_markup(text: &str, html: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest
+            .find(';')
+            .filter(|&end| end <= 32)
+            .and_then(|end| decode_entity(&rest[1..end], html).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str, html: bool) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        _ if !html => return None,
+        "nbsp" => '\u{a0}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "euro" => '€',
+        _ => return None,
+    };
+    Some(c)
+}
+
+fn escape_csv(text: &str) -> String {
+    let needs_quotes =
+        text.contains([',', '"', '\n', '\r']) || text.starts_with(' ') || text.ends_with(' ');
+    if needs_quotes {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn unescape_csv(text: &str) -> Result<String> {
+    let Some(inner) = text
+        .strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .filter(|_| text.len() >= 2)
+    else {
+        return Ok(text.to_string());
+    };
+
+    if inner.replace("\"\"", "").contains('"') {
+        bail!("Unescaped quote inside quoted CSV field");
+    }
+    Ok(inner.replace("\"\"", "\""))
+}
+
+fn unescape_json(text: &str) -> Result<String> {
+    let trimmed = text.trim();
+    let quoted = if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+        trimmed.to_string()
+    } else {
+        format!("\"{}\"", text)
+    };
+    serde_json::from_str(&quoted).context("Invalid JSON string escape")
+}
+
+fn escape_c(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\u{7}' => out.push_str("\\a"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\u{b}' => out.push_str("\\v"),
+            // Octal escapes always stop after three digits, unlike \x
+            c if (c as u32) < 0x20 || c == '\u{7f}' => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn unescape_c(text: &str) -> Result<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        let Some(e) = chars.next() else {
+            bail!("Trailing backslash");
+        };
+        let byte = match e {
+            'n' => b'\n',
+            't' => b'\t',
+            'r' => b'\r',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            'e' => 0x1b,
+            '\\' | '\'' | '"' | '?' => e as u8,
+            '0'..='7' => {
+                let mut value = e.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) => {
+                            value = value * 8 + d;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                u8::try_from(value).context("Octal escape out of range")?
+            }
+            'x' => {
+                let mut value = 0u32;
+                let mut digits = 0;
+                while let Some(d) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    value = value * 16 + d;
+                    digits += 1;
+                    chars.next();
+                }
+                if digits == 0 {
+                    bail!("\\x used with no following hex digits");
+                }
+                u8::try_from(value).context("Hex escape out of range")?
+            }
+            'u' | 'U' => {
+                let len = if e == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                let code = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == len)
+                    .with_context(|| format!("Invalid \\{} escape", e))?;
+                let c = char::from_u32(code).context("Invalid Unicode code point")?;
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+            other => bail!("Unknown escape sequence \\{}", other),
+        };
+        bytes.push(byte);
+    }
+
+    String::from_utf8(bytes).context("Unescaped bytes are not valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(target: &str, original: &str) {
+        let escaped = escape(Source::Literal(original), target).unwrap();
+        assert_eq!(
+            unescape(Source::Literal(&escaped), target).unwrap(),
+            original,
+            "{}",
+            target
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_all_targets() {
+        let samples = [
+            "plain",
+            "line one\nline \"two\"\ttab",
+            "it's <b>&amp;</b> 100%",
+            "a,b \\ c",
+            "unicode: héllo 🚀",
+        ];
+        for target in ["json", "shell", "regex", "html", "xml", "csv", "c", "url"] {
+            for sample in samples {
+                roundtrip(target, sample);
+            }
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            escape(Source::Literal("say \"hi\"\n"), "json").unwrap(),
+            r#""say \"hi\"\n""#
+        );
+        assert_eq!(unescape(Source::Literal(r"aé\n"), "json").unwrap(), "aé\n");
+    }
+
+    #[test]
+    fn test_shell() {
+        assert_eq!(
+            escape(Source::Literal("safe/path-1.txt"), "shell").unwrap(),
+            "safe/path-1.txt"
+        );
+        assert_eq!(
+            escape(Source::Literal("it's $HOME"), "shell").unwrap(),
+            r"'it'\''s $HOME'"
+        );
+        assert_eq!(escape(Source::Literal(""), "shell").unwrap(), "''");
+        assert_eq!(
+            unescape(Source::Literal(r#"a\ b"c\$d"$'\x41'"#), "shell").unwrap(),
+            "a bc$dA"
+        );
+    }
+
+    #[test]
+    fn test_regex() {
+        assert_eq!(
+            escape(Source::Literal("1.5*(a|b)"), "regex").unwrap(),
+            r"1\.5\*\(a\|b\)"
+        );
+        // `#`, `&`, `-` and `~` stay bare: escaping them is an error in JS `u` mode
+        assert_eq!(
+            escape(Source::Literal("a-b#c&d~[^$]"), "regex").unwrap(),
+            r"a-b#c&d~\[\^\$\]"
+        );
+        assert!(unescape(Source::Literal(r"\d+"), "regex").is_err());
+    }
+
+    #[test]
+    fn test_markup() {
+        assert_eq!(
+            escape(Source::Literal("<a href='x'>"), "html").unwrap(),
+            "&lt;a href=&#39;x&#39;&gt;"
+        );
+        assert_eq!(escape(Source::Literal("'"), "xml").unwrap(), "&apos;");
+        assert_eq!(
+            unescape(Source::Literal("&copy; &#x1F680; &#65; &bogus;"), "html").unwrap(),
+            "© 🚀 A &bogus;"
+        );
+        assert_eq!(
+            unescape(Source::Literal("&copy;"), "xml").unwrap(),
+            "&copy;"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(escape(Source::Literal("plain"), "csv").unwrap(), "plain");
+        assert_eq!(
+            escape(Source::Literal("a,\"b\""), "csv").unwrap(),
+            r#""a,""b""""#
+        );
+        assert!(unescape(Source::Literal(r#""a"b""#), "csv").is_err());
+    }
+
+    #[test]
+    fn test_c() {
+        assert_eq!(
+            escape(Source::Literal("tab\there\u{1}"), "c").unwrap(),
+            r#""tab\there\001""#
+        );
+        assert_eq!(
+            unescape(Source::Literal(r"\x41\101é\n"), "c").unwrap(),
+            "AAé\n"
+        );
+        assert!(unescape(Source::Literal(r"\q"), "c").is_err());
+    }
+
+    #[test]
+    fn test_unknown_target() {
+        assert!(escape(Source::Literal("x"), "yaml").is_err());
+    }
+}
@@ -1,7 +1,10 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, Result};
 use md5::Md5;
 use sha2::{Digest, Sha256, Sha512};
-use std::fs;
+use std::path::Path;
+
+use super::input::{read_bytes, Source};
+use super::json;
 
 /// Generate MD5 hash
 pub fn md5(input: &str) -> Result<String> {
@@ -27,31 +30,44 @@ pub fn sha512(input: &str) -> Result<String> {
     Ok(format!("{:x}", result))
 }
 
-/// Hash a file with specified algorithm
-pub fn hash_file(path: &str, algorithm: &str) -> Result<String> {
-    let bytes = fs::read(path)
-        .with_context(|| format!("Failed to read file: {}", path))?;
+/// Hash literal text, a file or stdin, optionally as the RFC 8785 canonical
+/// form of its JSON
+pub fn hash(input: Source<'_>, algorithm: &str, canonical: bool) -> Result<String> {
+    let bytes = if canonical {
+        json::canonicalize(input)?.into_bytes()
+    } else {
+        read_bytes(input)?
+    };
+    digest(&bytes, algorithm)
+}
 
+/// Hash a file with specified algorithm, optionally canonicalizing it as JSON first
+pub fn hash_file(path: &str, algorithm: &str, canonical: bool) -> Result<String> {
+    let hash = hash(Source::File(Path::new(path)), algorithm, canonical)?;
+    Ok(format!("{} ({}) = {}", algorithm.to_uppercase(), path, hash))
+}
+
+fn digest(bytes: &[u8], algorithm: &str) -> Result<String> {
     let hash = match algorithm.to_lowercase().as_str() {
         "md5" => {
             let mut hasher = Md5::new();
-            hasher.update(&bytes);
+            hasher.update(bytes);
             format!("{:x}", hasher.finalize())
         }
         "sha256" => {
             let mut hasher = Sha256::new();
-            hasher.update(&bytes);
+            hasher.update(bytes);
             format!("{:x}", hasher.finalize())
         }
         "sha512" => {
             let mut hasher = Sha512::new();
-            hasher.update(&bytes);
+            hasher.update(bytes);
             format!("{:x}", hasher.finalize())
         }
         _ => bail!("Unknown algorithm: {}. Use md5, sha256, or sha512.", algorithm),
     };
 
-    Ok(format!("{} ({}) = {}", algorithm.to_uppercase(), path, hash))
+    Ok(hash)
 }
 
 #[cfg(test)]
@@ -73,6 +89,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_canonical_ignores_formatting() {
+        let a = hash(Source::Literal(r#"{"b": 1, "a": [1.0, "x"]}"#), "sha256", true).unwrap();
+        let b = hash(Source::Literal(r#"{"a":[1,"x"],"b":1}"#), "sha256", true).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, sha256(r#"{"a":[1,"x"],"b":1}"#).unwrap());
+    }
+
+    #[test]
+    fn test_hash_file_named_with_at() {
+        // Relative, as typed on the command line
+        let path = format!("@devkit-hash-test-{}.json", std::process::id());
+        std::fs::write(&path, r#"{"b":1,"a":2}"#).unwrap();
+        let plain = hash_file(&path, "md5", false);
+        let canonical = hash_file(&path, "md5", true);
+        let from_source = hash(Source::File(Path::new(&path)), "md5", false);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(plain.unwrap().ends_with(&md5(r#"{"b":1,"a":2}"#).unwrap()));
+        assert!(canonical.unwrap().ends_with(&md5(r#"{"a":2,"b":1}"#).unwrap()));
+        assert_eq!(from_source.unwrap(), md5(r#"{"b":1,"a":2}"#).unwrap());
+    }
+
     #[test]
     fn test_sha512() {
         let result = sha512("hello").unwrap();
@@ -0,0 +1,167 @@
+//! Input shared by the subcommands that read a document (JSON, YAML, TOML,
+//! CSV or text to escape).
+//!
+//! Arguments are literal text unless they use curl-style `@` syntax:
+//! `@path` reads a file, `@-` reads stdin and `@@text` is the literal `@text`.
+//! With the `--file` flag the argument is a path taken as-is, so
+//! `--file @x.json` reads the file named `@x.json`. Strings for `url encode`
+//! and `url decode` (`@babel/core`), URLs being edited, domains and Base64
+//! text are always literal.
+
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Where a document argument points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source<'a> {
+    Literal(&'a str),
+    File(&'a Path),
+    Stdin,
+}
+
+/// Classify a document argument
+pub fn source(arg: &str) -> Source<'_> {
+    if arg.starts_with("@@") {
+        // Keep one `@` of the escape
+        Source::Literal(&arg[1..])
+    } else if arg == "@-" {
+        Source::Stdin
+    } else if let Some(path) = arg.strip_prefix('@') {
+        Source::File(Path::new(path))
+    } else {
+        Source::Literal(arg)
+    }
+}
+
+/// Whether the input comes from a file or stdin rather than literal text
+pub fn is_stream(input: Source<'_>) -> bool {
+    !matches!(input, Source::Literal(_))
+}
+
+fn check_exists(path: &Path) -> Result<()> {
+    if !path.exists() {
+        bail!("File not found: {}", path.display());
+    }
+    Ok(())
+}
+
+/// Read the whole input into a string
+pub fn read_input(input: Source<'_>) -> Result<String> {
+    match input {
+        Source::Literal(text) => Ok(text.to_string()),
+        Source::File(path) => {
+            check_exists(path)?;
+            fs::read_to_string(path)
+                .with_context(|| format!("Failed to read file: {}", path.display()))
+        }
+        Source::Stdin => {
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .context("Failed to read stdin")?;
+            Ok(content)
+        }
+    }
+}
+
+/// Read the whole input as bytes, for content that need not be UTF-8
+pub fn read_bytes(input: Source<'_>) -> Result<Vec<u8>> {
+    match input {
+        Source::Literal(text) => Ok(text.as_bytes().to_vec()),
+        Source::File(path) => {
+            check_exists(path)?;
+            fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))
+        }
+        Source::Stdin => {
+            let mut content = Vec::new();
+            io::stdin()
+                .read_to_end(&mut content)
+                .context("Failed to read stdin")?;
+            Ok(content)
+        }
+    }
+}
+
+/// Open the input as a buffered reader without loading it into memory
+pub fn open_input<'a>(input: Source<'a>) -> Result<Box<dyn BufRead + 'a>> {
+    match input {
+        Source::Literal(text) => Ok(Box::new(text.as_bytes())),
+        Source::File(path) => {
+            check_exists(path)?;
+            let file = File::open(path)
+                .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            Ok(Box::new(BufReader::new(file)))
+        }
+        Source::Stdin => Ok(Box::new(io::stdin().lock())),
+    }
+}
+
+/// Suggestion to append to a parse error when literal input happens to
+/// match an existing file name, which used to be read implicitly
+pub fn file_hint(input: Source<'_>) -> String {
+    match input {
+        Source::Literal(text) if !text.is_empty() && Path::new(text).is_file() => {
+            // `@` before a name that starts with `@` would be the literal escape
+            if text.starts_with('@') {
+                format!(" (a file named '{}' exists; use --file to read it)", text)
+            } else {
+                format!(
+                    " (a file named '{}' exists; use @{} or --file to read it)",
+                    text, text
+                )
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source() {
+        assert_eq!(source("true"), Source::Literal("true"));
+        assert_eq!(source("@data.json"), Source::File(Path::new("data.json")));
+        assert_eq!(source("@-"), Source::Stdin);
+        assert_eq!(source("@@handle"), Source::Literal("@handle"));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let err = read_input(source("@/definitely/not/here.json")).unwrap_err();
+        assert!(err.to_string().starts_with("File not found"));
+    }
+
+    #[test]
+    fn test_literal_never_reads_file() {
+        // Cargo.toml exists in the working directory during tests
+        assert_eq!(read_input(source("Cargo.toml")).unwrap(), "Cargo.toml");
+        assert!(file_hint(source("Cargo.toml")).contains("@Cargo.toml"));
+        assert!(read_input(source("@Cargo.toml"))
+            .unwrap()
+            .contains("[package]"));
+    }
+
+    #[test]
+    fn test_file_named_with_at() {
+        // Relative to the working directory, like a name typed on the command line
+        let name = format!("@devkit-input-test-{}.json", std::process::id());
+        fs::write(&name, "{}").unwrap();
+
+        // What `--file` resolves to
+        let read = read_input(Source::File(Path::new(&name)));
+        // Without it the doubled `@` is the literal escape
+        let escaped = format!("@{}", name);
+        let literal = read_input(source(&escaped));
+        let hint = file_hint(source(&escaped));
+        fs::remove_file(&name).unwrap();
+
+        assert_eq!(read.unwrap(), "{}");
+        assert_eq!(literal.unwrap(), name);
+        assert!(hint.contains("use --file"), "{}", hint);
+        assert!(!hint.contains("@@"), "{}", hint);
+    }
+}
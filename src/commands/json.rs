@@ -1,96 +1,154 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use colored::*;
 use serde_json::Value;
 use std::fs;
-use std::path::Path;
-
-/// Read input from string or file
-fn read_input(input: &str) -> Result<String> {
-    if Path::new(input).exists() {
-        fs::read_to_string(input)
-            .with_context(|| format!("Failed to read file: {}", input))
-    } else {
-        Ok(input.to_string())
-    }
+use std::io::{self, Write};
+
+use super::input::{file_hint, is_stream, open_input, read_input, Source};
+use super::Output;
+use pointer::ValueType;
+
+pub mod canonical;
+pub mod codegen;
+pub mod pointer;
+pub mod stats;
+pub mod stream;
+
+/// Parse the whole input into a `Value`
+pub(crate) fn parse_input(input: Source<'_>) -> Result<Value> {
+    let content = read_input(input)?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Invalid JSON syntax{}", file_hint(input)))
 }
 
 /// Format/prettify JSON
-pub fn format(input: &str, _indent: usize) -> Result<String> {
-    let content = read_input(input)?;
-    
-    let value: Value = serde_json::from_str(&content)
-        .context("Invalid JSON syntax")?;
+pub fn format(input: Source<'_>, _indent: usize) -> Result<String> {
+    let value = parse_input(input)?;
     
     // Use serde_json's built-in pretty printing
     serde_json::to_string_pretty(&value).context("Failed to format JSON")
 }
 
 /// Minify JSON (remove all whitespace)
-pub fn minify(input: &str) -> Result<String> {
-    let content = read_input(input)?;
-    
-    let value: Value = serde_json::from_str(&content)
-        .context("Invalid JSON syntax")?;
-    
-    serde_json::to_string(&value).context("Failed to minify JSON")
+///
+/// Files and stdin are streamed straight to stdout so arbitrarily large
+/// documents run in bounded memory; output written before a syntax error is
+/// not retracted.
+pub fn minify(input: Source<'_>) -> Result<Output> {
+    let reader = open_input(input)?;
+
+    if is_stream(input) {
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        stream::minify(reader, &mut out).context("Invalid JSON syntax")?;
+        writeln!(out)?;
+        out.flush()?;
+        return Ok(Output::Written);
+    }
+
+    let mut out = Vec::new();
+    stream::minify(reader, &mut out)
+        .with_context(|| format!("Invalid JSON syntax{}", file_hint(input)))?;
+    let text = String::from_utf8(out).context("Failed to minify JSON")?;
+    Ok(Output::Text(text))
 }
 
 /// Validate JSON syntax
-pub fn validate(input: &str) -> Result<String> {
-    let content = read_input(input)?;
-    
-    match serde_json::from_str::<Value>(&content) {
-        Ok(value) => {
-            let obj_count = count_objects(&value);
-            let arr_count = count_arrays(&value);
-            let key_count = count_keys(&value);
-            
-            Ok(format!(
-                "{} JSON is valid!\n  {} objects\n  {} arrays\n  {} keys",
-                "✓".green().bold(),
-                obj_count,
-                arr_count,
-                key_count
-            ))
-        }
-        Err(e) => {
-            Ok(format!(
-                "{} Invalid JSON at line {}, column {}\n  {}",
-                "✗".red().bold(),
-                e.line(),
-                e.column(),
-                e
-            ))
-        }
+pub fn validate(input: Source<'_>) -> Result<String> {
+    let reader = open_input(input)?;
+
+    match stream::scan(reader) {
+        Ok(counts) => Ok(format!(
+            "{} JSON is valid!\n  {} objects\n  {} arrays\n  {} keys",
+            "✓".green().bold(),
+            counts.objects,
+            counts.arrays,
+            counts.keys
+        )),
+        Err(e) => Ok(format!(
+            "{} Invalid JSON at line {}, column {}\n  {}{}",
+            "✗".red().bold(),
+            e.line,
+            e.column,
+            e,
+            file_hint(input)
+        )),
     }
 }
 
-fn count_objects(value: &Value) -> usize {
-    match value {
-        Value::Object(map) => {
-            1 + map.values().map(count_objects).sum::<usize>()
+/// Report size and shape statistics, listing the `top` largest arrays and
+/// heaviest subtrees
+pub fn stats(input: Source<'_>, top: usize) -> Result<String> {
+    let reader = open_input(input)?;
+    let stats = stats::collect(reader, top)
+        .with_context(|| format!("Invalid JSON syntax{}", file_hint(input)))?;
+    Ok(stats.render())
+}
+
+/// Generate type definitions (Rust, TypeScript or Go) from a JSON sample
+pub fn codegen(input: Source<'_>, lang: &str, name: &str) -> Result<String> {
+    let value = parse_input(input)?;
+    codegen::generate(&value, lang, name)
+}
+
+/// Serialize JSON in RFC 8785 canonical form (for hashing and signing)
+pub fn canonicalize(input: Source<'_>) -> Result<String> {
+    let content = read_input(input)?;
+    let value = canonical::parse(&content).map_err(|e| {
+        if e.is_data() {
+            anyhow!("Cannot canonicalize: {}", e)
+        } else {
+            anyhow!(e).context(format!("Invalid JSON syntax{}", file_hint(input)))
         }
-        Value::Array(arr) => arr.iter().map(count_objects).sum(),
-        _ => 0,
-    }
+    })?;
+    canonical::canonicalize(&value)
 }
 
-fn count_arrays(value: &Value) -> usize {
-    match value {
-        Value::Object(map) => map.values().map(count_arrays).sum(),
-        Value::Array(arr) => 1 + arr.iter().map(count_arrays).sum::<usize>(),
-        _ => 0,
+/// Print the value at a JSON pointer (strings unquoted with `raw`)
+pub fn get(input: Source<'_>, path: &str, raw: bool) -> Result<String> {
+    let value = parse_input(input)?;
+    let found = pointer::get(&value, path)?;
+
+    match found {
+        Value::String(s) if raw => Ok(s.clone()),
+        other => serde_json::to_string_pretty(other).context("Failed to format JSON"),
     }
 }
 
-fn count_keys(value: &Value) -> usize {
-    match value {
-        Value::Object(map) => {
-            map.len() + map.values().map(count_keys).sum::<usize>()
-        }
-        Value::Array(arr) => arr.iter().map(count_keys).sum(),
-        _ => 0,
+/// Set the value at a JSON pointer
+pub fn set(
+    input: Source<'_>,
+    path: &str,
+    value: &str,
+    value_type: ValueType,
+    parents: bool,
+    in_place: bool,
+) -> Result<String> {
+    let mut document = parse_input(input)?;
+    let value = pointer::parse_value(value, value_type)?;
+    pointer::set(&mut document, path, value, parents)?;
+    write_document(input, &document, in_place, &format!("Set {}", path))
+}
+
+/// Delete the value at a JSON pointer
+pub fn delete(input: Source<'_>, path: &str, in_place: bool) -> Result<String> {
+    let mut document = parse_input(input)?;
+    pointer::delete(&mut document, path)?;
+    write_document(input, &document, in_place, &format!("Deleted {}", path))
+}
+
+/// Print an edited document, or write it back to its file with `in_place`
+fn write_document(input: Source<'_>, document: &Value, in_place: bool, action: &str) -> Result<String> {
+    let json = serde_json::to_string_pretty(document).context("Failed to format JSON")?;
+    if !in_place {
+        return Ok(json);
     }
+
+    let Source::File(path) = input else {
+        bail!("--in-place needs a file input (@path or --file)");
+    };
+    fs::write(path, json + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(format!("✓ {} in {}", action, path.display()))
 }
 
 #[cfg(test)]
@@ -100,7 +158,7 @@ mod tests {
     #[test]
     fn test_format() {
         let input = r#"{"name":"test","value":123}"#;
-        let result = format(input, 2).unwrap();
+        let result = format(Source::Literal(input), 2).unwrap();
         assert!(result.contains('\n'));
         assert!(result.contains("name"));
     }
@@ -111,7 +169,9 @@ mod tests {
             "name": "test",
             "value": 123
         }"#;
-        let result = minify(input).unwrap();
+        let Output::Text(result) = minify(Source::Literal(input)).unwrap() else {
+            panic!("literal input is returned, not streamed");
+        };
         assert!(!result.contains('\n'));
         assert!(!result.contains("  "));
     }
@@ -119,14 +179,14 @@ mod tests {
     #[test]
     fn test_validate_valid() {
         let input = r#"{"valid": true}"#;
-        let result = validate(input).unwrap();
+        let result = validate(Source::Literal(input)).unwrap();
         assert!(result.contains("valid"));
     }
 
     #[test]
     fn test_validate_invalid() {
         let input = r#"{"invalid": }"#;
-        let result = validate(input).unwrap();
+        let result = validate(Source::Literal(input)).unwrap();
         assert!(result.contains("Invalid"));
     }
 }
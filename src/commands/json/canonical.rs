@@ -0,0 +1,271 @@
+//! RFC 8785 JSON Canonicalization Scheme (JCS).
+//!
+//! Produces a byte-exact serialization suitable for hashing and signing:
+//! no whitespace, object keys sorted by their UTF-16 code units, numbers
+//! formatted like ECMAScript's `Number.prototype.toString`, and strings with
+//! only the escapes `JSON.stringify` emits.
+
+use anyhow::{bail, Result};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+/// Parse JSON, rejecting duplicate member names
+///
+/// RFC 8785 only covers I-JSON (RFC 7493), where names must be unique;
+/// silently keeping the last value would hash a document other tools see
+/// differently.
+pub fn parse(content: &str) -> serde_json::Result<Value> {
+    serde_json::from_str::<Unique>(content).map(|unique| unique.0)
+}
+
+struct Unique(Value);
+
+impl<'de> Deserialize<'de> for Unique {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(UniqueVisitor).map(Unique)
+    }
+}
+
+struct UniqueVisitor;
+
+impl<'de> Visitor<'de> for UniqueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Value, E> {
+        Ok(Value::from(n))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Value, E> {
+        Ok(Value::from(n))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(n).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(Unique(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = Map::new();
+        while let Some(key) = access.next_key::<String>()? {
+            if map.contains_key(&key) {
+                return Err(de::Error::custom(format!(
+                    "duplicate member name {}",
+                    Value::String(key)
+                )));
+            }
+            let Unique(item) = access.next_value()?;
+            map.insert(key, item);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+/// Serialize a value in canonical form
+pub fn canonicalize(value: &Value) -> Result<String> {
+    let mut out = String::new();
+    write_value(&mut out, value)?;
+    Ok(out)
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<()> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(true) => out.push_str("true"),
+        Value::Bool(false) => out.push_str("false"),
+        Value::Number(n) => out.push_str(&format_number(n)?),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, item)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/// Format a number the way ECMAScript does (RFC 8785 section 3.2.2.3)
+fn format_number(n: &Number) -> Result<String> {
+    let f = match n.as_f64() {
+        Some(f) if f.is_finite() => f,
+        _ => bail!("Number {} cannot be represented as an IEEE 754 double", n),
+    };
+    Ok(format_f64(f))
+}
+
+fn format_f64(f: f64) -> String {
+    if f == 0.0 {
+        // Covers -0 as well
+        return "0".to_string();
+    }
+
+    // Rust's `{:e}` gives the shortest digits that round-trip, e.g. "1.25e-7"
+    let sci = format!("{:e}", f.abs());
+    let (mantissa, exponent) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // Position of the decimal point relative to the digits
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        format!("{}.{}", int, frac)
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let e = n - 1;
+        let sign = if e < 0 { '-' } else { '+' };
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            format!("{}e{}{}", first, sign, e.abs())
+        } else {
+            format!("{}.{}e{}{}", first, rest, sign, e.abs())
+        }
+    };
+
+    if f < 0.0 {
+        format!("-{}", body)
+    } else {
+        body
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canon(json: &str) -> String {
+        canonicalize(&serde_json::from_str(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_sorts_and_strips_whitespace() {
+        assert_eq!(
+            canon(r#"{ "b": [1, {"z": 1, "a": 2}], "a": null }"#),
+            r#"{"a":null,"b":[1,{"a":2,"z":1}]}"#
+        );
+    }
+
+    #[test]
+    fn test_utf16_key_order() {
+        // U+1F600 is encoded as surrogates starting D83D, so it sorts before
+        // U+FB33 even though its code point is larger (RFC 8785 section 3.2.3)
+        assert_eq!(
+            canon("{\"\u{fb33}\": 5, \"😀\": 2, \"€\": 1, \"\\r\": 3, \"1\": 4}"),
+            "{\"\\r\":3,\"1\":4,\"€\":1,\"😀\":2,\"\u{fb33}\":5}"
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        // Examples from RFC 8785 appendix B
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (5e-324, "5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+            (1e21, "1e+21"),
+            (1e-7, "1e-7"),
+            (0.000001, "0.000001"),
+            (333333333.3333333, "333333333.3333333"),
+            (-1.5, "-1.5"),
+            (4.50, "4.5"),
+            (2e-3, "0.002"),
+            (1e23, "1e+23"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(format_f64(input), expected, "formatting {}", input);
+        }
+        assert_eq!(canon("[1.0, 100, 1E2, -0.0]"), "[1,100,100,0]");
+    }
+
+    #[test]
+    fn test_rejects_duplicate_names() {
+        let err = parse(r#"{"a": 1, "b": {"c": 2, "c": 3}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"duplicate member name "c" at line 1 column 26"#
+        );
+        assert_eq!(
+            parse(r#"{"a": {"c": 1}, "b": {"c": [2.5, null]}}"#).unwrap(),
+            serde_json::json!({"a": {"c": 1}, "b": {"c": [2.5, null]}})
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        assert_eq!(
+            canon(r#""\u0041\u001f\n\"\/\u2028é""#),
+            "\"A\\u001f\\n\\\"/\u{2028}é\""
+        );
+    }
+}
@@ -0,0 +1,630 @@
+//! Generate Rust, TypeScript or Go type definitions from sample JSON.
+//!
+//! The sample is reduced to a [`Shape`]: array elements are merged into one
+//! shape, fields missing from some objects become optional, and `null`
+//! alongside another type makes it nullable. Every nested object then gets a
+//! named type derived from the key it appears under.
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+/// Target language for generated types
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    Rust,
+    TypeScript,
+    Go,
+}
+
+impl Lang {
+    pub fn parse(name: &str) -> Result<Lang> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Ok(Lang::Rust),
+            "ts" | "typescript" => Ok(Lang::TypeScript),
+            "go" | "golang" => Ok(Lang::Go),
+            _ => bail!("Unsupported language: {}. Use rust, ts, or go.", name),
+        }
+    }
+
+    /// Type names the generated code already refers to
+    fn reserved(self) -> &'static [&'static str] {
+        match self {
+            Lang::Rust => &[
+                "Option",
+                "Vec",
+                "String",
+                "Box",
+                "Result",
+                "HashMap",
+                "Serialize",
+                "Deserialize",
+            ],
+            Lang::TypeScript | Lang::Go => &[],
+        }
+    }
+}
+
+/// Inferred structure of one or more sample values
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    /// Nothing seen yet (e.g. the elements of an empty array)
+    Unknown,
+    Null,
+    Bool,
+    Int,
+    Float,
+    String,
+    Array(Box<Type>),
+    Object(Vec<Field>),
+    /// Incompatible types seen at the same position
+    Mixed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Type {
+    shape: Shape,
+    nullable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    key: String,
+    ty: Type,
+    /// Missing from at least one sample object
+    optional: bool,
+}
+
+impl Type {
+    fn of(value: &Value) -> Type {
+        let shape = match value {
+            Value::Null => Shape::Null,
+            Value::Bool(_) => Shape::Bool,
+            Value::Number(n) if n.is_f64() => Shape::Float,
+            Value::Number(_) => Shape::Int,
+            Value::String(_) => Shape::String,
+            Value::Array(items) => {
+                let element = items.iter().map(Type::of).fold(
+                    Type {
+                        shape: Shape::Unknown,
+                        nullable: false,
+                    },
+                    Type::merge,
+                );
+                Shape::Array(Box::new(element))
+            }
+            Value::Object(map) => Shape::Object(
+                map.iter()
+                    .map(|(key, item)| Field {
+                        key: key.clone(),
+                        ty: Type::of(item),
+                        optional: false,
+                    })
+                    .collect(),
+            ),
+        };
+        Type {
+            shape,
+            nullable: false,
+        }
+    }
+
+    fn merge(self, other: Type) -> Type {
+        let nullable = self.nullable || other.nullable;
+        let shape = match (self.shape, other.shape) {
+            (Shape::Unknown, s) | (s, Shape::Unknown) => s,
+            (Shape::Null, Shape::Null) => Shape::Null,
+            (Shape::Null, s) | (s, Shape::Null) => {
+                return Type {
+                    shape: s,
+                    nullable: true,
+                }
+            }
+            (Shape::Int, Shape::Float) | (Shape::Float, Shape::Int) => Shape::Float,
+            (Shape::Array(a), Shape::Array(b)) => Shape::Array(Box::new(a.merge(*b))),
+            (Shape::Object(a), Shape::Object(b)) => Shape::Object(merge_fields(a, b)),
+            (a, b) if a == b => a,
+            _ => Shape::Mixed,
+        };
+        Type { shape, nullable }
+    }
+}
+
+fn merge_fields(mut fields: Vec<Field>, other: Vec<Field>) -> Vec<Field> {
+    let other_keys: HashSet<String> = other.iter().map(|f| f.key.clone()).collect();
+    for field in fields.iter_mut() {
+        if !other_keys.contains(&field.key) {
+            field.optional = true;
+        }
+    }
+
+    for incoming in other {
+        match fields.iter_mut().find(|f| f.key == incoming.key) {
+            Some(existing) => {
+                let ty = std::mem::replace(
+                    &mut existing.ty,
+                    Type {
+                        shape: Shape::Unknown,
+                        nullable: false,
+                    },
+                );
+                existing.ty = ty.merge(incoming.ty);
+                existing.optional |= incoming.optional;
+            }
+            None => fields.push(Field {
+                optional: true,
+                ..incoming
+            }),
+        }
+    }
+    fields
+}
+
+/// A field type after nested objects have been given names
+#[derive(Debug, Clone)]
+enum TypeRef {
+    Any,
+    Bool,
+    Int,
+    Float,
+    String,
+    Array(Box<TypeRef>, bool),
+    Named(String),
+}
+
+struct Def {
+    name: String,
+    fields: Vec<(String, TypeRef, bool, bool)>,
+}
+
+/// Assigns names to nested objects and collects their definitions
+struct Namer {
+    defs: Vec<Def>,
+    used: HashSet<String>,
+}
+
+impl Namer {
+    fn unique(&mut self, hint: &str) -> String {
+        let base = match pascal_case(hint) {
+            name if name.is_empty() => "Item".to_string(),
+            name if name.starts_with(|c: char| c.is_ascii_digit()) => format!("T{}", name),
+            name => name,
+        };
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.used.contains(&name) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        self.used.insert(name.clone());
+        name
+    }
+
+    fn lower(&mut self, ty: &Type, hint: &str) -> TypeRef {
+        match &ty.shape {
+            Shape::Unknown | Shape::Null | Shape::Mixed => TypeRef::Any,
+            Shape::Bool => TypeRef::Bool,
+            Shape::Int => TypeRef::Int,
+            Shape::Float => TypeRef::Float,
+            Shape::String => TypeRef::String,
+            Shape::Array(element) => {
+                let inner = self.lower(element, &singular(hint));
+                TypeRef::Array(Box::new(inner), element.nullable)
+            }
+            Shape::Object(fields) => {
+                let name = self.unique(hint);
+                // Reserve the slot first so parents are emitted before children
+                let index = self.defs.len();
+                self.defs.push(Def {
+                    name: name.clone(),
+                    fields: Vec::new(),
+                });
+                let lowered = fields
+                    .iter()
+                    .map(|f| {
+                        let nullable = f.ty.nullable || f.ty.shape == Shape::Null;
+                        (
+                            f.key.clone(),
+                            self.lower(&f.ty, &f.key),
+                            nullable,
+                            f.optional,
+                        )
+                    })
+                    .collect();
+                self.defs[index].fields = lowered;
+                TypeRef::Named(name)
+            }
+        }
+    }
+}
+
+/// Generate type definitions for `value` in the given language
+pub fn generate(value: &Value, lang: &str, root_name: &str) -> Result<String> {
+    let lang = Lang::parse(lang)?;
+    let ty = Type::of(value);
+
+    let mut namer = Namer {
+        defs: Vec::new(),
+        used: lang
+            .reserved()
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+    };
+    let root = match &ty.shape {
+        Shape::Object(_) => namer.lower(&ty, root_name),
+        Shape::Array(element) if matches!(element.shape, Shape::Object(_)) => {
+            let root = pascal_case(root_name);
+            namer.used.insert(root.clone());
+            let item = namer.lower(element, &format!("{}Item", root));
+            TypeRef::Array(Box::new(item), element.nullable)
+        }
+        _ => bail!("Code generation needs an object or an array of objects"),
+    };
+
+    let mut out = String::new();
+    match lang {
+        Lang::Rust => emit_rust(&mut out, &namer.defs),
+        Lang::TypeScript => emit_ts(&mut out, &namer.defs),
+        Lang::Go => emit_go(&mut out, &namer.defs),
+    }
+
+    // A top-level array gets an alias for the whole document
+    if let TypeRef::Array(..) = root {
+        let name = pascal_case(root_name);
+        let _ = match lang {
+            Lang::Rust => writeln!(out, "pub type {} = {};", name, rust_type(&root)),
+            Lang::TypeScript => writeln!(out, "export type {} = {};", name, ts_type(&root)),
+            Lang::Go => writeln!(out, "type {} {}", name, go_type(&root)),
+        };
+    }
+
+    Ok(out.trim_end().to_string())
+}
+
+fn emit_rust(out: &mut String, defs: &[Def]) {
+    out.push_str("use serde::{Deserialize, Serialize};\n\n");
+    for def in defs {
+        let _ = writeln!(out, "#[derive(Debug, Clone, Serialize, Deserialize)]");
+        let _ = writeln!(out, "pub struct {} {{", def.name);
+        let mut seen = HashSet::new();
+        for (key, ty, nullable, optional) in &def.fields {
+            let mut ident = rust_ident(key);
+            while !seen.insert(ident.clone()) {
+                ident.push('_');
+            }
+            if ident.trim_start_matches("r#") != key {
+                let _ = writeln!(out, "    #[serde(rename = {:?})]", key);
+            }
+            let mut ty = rust_type(ty);
+            if *nullable || *optional {
+                ty = format!("Option<{}>", ty);
+            }
+            let _ = writeln!(out, "    pub {}: {},", ident, ty);
+        }
+        out.push_str("}\n\n");
+    }
+}
+
+fn rust_type(ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Any => "serde_json::Value".to_string(),
+        TypeRef::Bool => "bool".to_string(),
+        TypeRef::Int => "i64".to_string(),
+        TypeRef::Float => "f64".to_string(),
+        TypeRef::String => "String".to_string(),
+        TypeRef::Array(inner, true) => format!("Vec<Option<{}>>", rust_type(inner)),
+        TypeRef::Array(inner, false) => format!("Vec<{}>", rust_type(inner)),
+        TypeRef::Named(name) => name.clone(),
+    }
+}
+
+fn rust_ident(key: &str) -> String {
+    const KEYWORDS: [&str; 48] = [
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+        "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override",
+        "priv", "try", "typeof", "unsized", "virtual", "yield",
+    ];
+    let ident = match snake_case(key) {
+        s if s.is_empty() => "field".to_string(),
+        s if s.starts_with(|c: char| c.is_ascii_digit()) => format!("field_{}", s),
+        s => s,
+    };
+    if matches!(ident.as_str(), "self" | "super" | "crate") {
+        format!("{}_", ident)
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{}", ident)
+    } else {
+        ident
+    }
+}
+
+fn emit_ts(out: &mut String, defs: &[Def]) {
+    for def in defs {
+        let _ = writeln!(out, "export interface {} {{", def.name);
+        for (key, ty, nullable, optional) in &def.fields {
+            let plain = !key.is_empty()
+                && !key.starts_with(|c: char| c.is_ascii_digit())
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+            let name = if plain {
+                key.clone()
+            } else {
+                format!("{:?}", key)
+            };
+            let mut ty = ts_type(ty);
+            if *nullable {
+                ty.push_str(" | null");
+            }
+            let mark = if *optional { "?" } else { "" };
+            let _ = writeln!(out, "  {}{}: {};", name, mark, ty);
+        }
+        out.push_str("}\n\n");
+    }
+}
+
+fn ts_type(ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Any => "unknown".to_string(),
+        TypeRef::Bool => "boolean".to_string(),
+        TypeRef::Int | TypeRef::Float => "number".to_string(),
+        TypeRef::String => "string".to_string(),
+        TypeRef::Array(inner, true) => format!("({} | null)[]", ts_type(inner)),
+        TypeRef::Array(inner, false) => format!("{}[]", ts_type(inner)),
+        TypeRef::Named(name) => name.clone(),
+    }
+}
+
+fn emit_go(out: &mut String, defs: &[Def]) {
+    for def in defs {
+        let mut seen = HashSet::new();
+        let rows: Vec<(String, String, String)> = def
+            .fields
+            .iter()
+            .map(|(key, ty, nullable, optional)| {
+                let mut name = go_ident(key);
+                while !seen.insert(name.clone()) {
+                    name.push('_');
+                }
+                let mut ty_name = go_type(ty);
+                let pointer =
+                    (*nullable || *optional) && !matches!(ty, TypeRef::Any | TypeRef::Array(..));
+                if pointer {
+                    ty_name.insert(0, '*');
+                }
+                let omit = if *optional { ",omitempty" } else { "" };
+                (name, ty_name, format!("`json:\"{}{}\"`", key, omit))
+            })
+            .collect();
+
+        // Align columns the way gofmt does
+        let name_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+        let type_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0);
+        let _ = writeln!(out, "type {} struct {{", def.name);
+        for (name, ty, tag) in rows {
+            let _ = writeln!(
+                out,
+                "\t{:name_width$} {:type_width$} {}",
+                name,
+                ty,
+                tag,
+                name_width = name_width,
+                type_width = type_width
+            );
+        }
+        out.push_str("}\n\n");
+    }
+}
+
+fn go_type(ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Any => "interface{}".to_string(),
+        TypeRef::Bool => "bool".to_string(),
+        TypeRef::Int => "int64".to_string(),
+        TypeRef::Float => "float64".to_string(),
+        TypeRef::String => "string".to_string(),
+        TypeRef::Array(inner, nullable) => {
+            let pointer = *nullable && !matches!(**inner, TypeRef::Any | TypeRef::Array(..));
+            format!("[]{}{}", if pointer { "*" } else { "" }, go_type(inner))
+        }
+        TypeRef::Named(name) => name.clone(),
+    }
+}
+
+fn go_ident(key: &str) -> String {
+    const INITIALISMS: [&str; 12] = [
+        "ID", "URL", "URI", "API", "HTTP", "HTTPS", "JSON", "XML", "SQL", "UUID", "IP", "HTML",
+    ];
+    let name: String = words(key)
+        .iter()
+        .map(|word| {
+            let upper = word.to_uppercase();
+            if INITIALISMS.contains(&upper.as_str()) {
+                upper
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect();
+    match name {
+        n if n.is_empty() => "Field".to_string(),
+        n if n.starts_with(|c: char| c.is_ascii_digit()) => format!("F{}", n),
+        n => n,
+    }
+}
+
+/// Split an identifier into words at separators and case changes
+fn words(s: &str) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+fn pascal_case(s: &str) -> String {
+    words(s).iter().map(|w| capitalize(w)).collect()
+}
+
+fn snake_case(s: &str) -> String {
+    words(s)
+        .iter()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Naive English singular, used to name array element types
+fn singular(word: &str) -> String {
+    if let Some(stem) = word.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if word.ends_with("sses")
+        || word.ends_with("xes")
+        || word.ends_with("ches")
+        || word.ends_with("shes")
+    {
+        word[..word.len() - 2].to_string()
+    } else if word.ends_with('s') && !word.ends_with("ss") && word.len() > 1 {
+        word[..word.len() - 1].to_string()
+    } else {
+        format!("{}Item", word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Value {
+        serde_json::from_str(
+            r#"{
+                "userId": 1,
+                "type": "admin",
+                "addresses": [
+                    {"city": "Oslo", "zip": "0150"},
+                    {"city": "Bergen", "geo": {"lat": 60.4, "lng": 5}}
+                ],
+                "nickname": null,
+                "scores": [1, 2.5]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rust() {
+        let code = generate(&sample(), "rust", "Root").unwrap();
+        assert!(code.contains("pub struct Root {"));
+        assert!(code.contains("#[serde(rename = \"userId\")]\n    pub user_id: i64,"));
+        assert!(code.contains("pub r#type: String,"));
+        assert!(code.contains("pub addresses: Vec<Address>,"));
+        assert!(code.contains("pub zip: Option<String>,"));
+        assert!(code.contains("pub geo: Option<Geo>,"));
+        assert!(code.contains("pub lng: i64,"));
+        assert!(code.contains("pub nickname: Option<serde_json::Value>,"));
+        assert!(code.contains("pub scores: Vec<f64>,"));
+        // Parents come before the types they reference
+        assert!(code.find("struct Root").unwrap() < code.find("struct Address").unwrap());
+    }
+
+    #[test]
+    fn test_rust_avoids_std_names_and_keywords() {
+        let value = serde_json::json!({
+            "options": [{"try": 1, "yield": true, "gen": "x", "macro": null}],
+            "result": {"ok": true},
+            "string": {"value": "s"},
+        });
+        let code = generate(&value, "rust", "Root").unwrap();
+        assert!(code.contains("pub struct Option2 {"));
+        assert!(code.contains("pub options: Vec<Option2>,"));
+        assert!(code.contains("pub struct Result2 {"));
+        assert!(code.contains("pub string: String2,"));
+        assert!(!code.contains("struct Option {"));
+        assert!(code.contains("pub r#try: i64,"));
+        assert!(code.contains("pub r#yield: bool,"));
+        assert!(code.contains("pub r#gen: String,"));
+        assert!(code.contains("pub r#macro: Option<serde_json::Value>,"));
+        // Other languages keep the plain names
+        let ts = generate(&value, "ts", "Root").unwrap();
+        assert!(ts.contains("export interface Option {"));
+    }
+
+    #[test]
+    fn test_typescript() {
+        let code = generate(&sample(), "ts", "Root").unwrap();
+        assert!(code.contains("export interface Address {"));
+        assert!(code.contains("  zip?: string;"));
+        assert!(code.contains("  nickname: unknown | null;"));
+        assert!(code.contains("  scores: number[];"));
+    }
+
+    #[test]
+    fn test_go() {
+        let code = generate(&sample(), "go", "Root").unwrap();
+        assert!(code.contains("type Root struct {"));
+        assert!(code.contains("UserID "));
+        assert!(code.contains("`json:\"userId\"`"));
+        assert!(code.contains("*Geo"));
+        assert!(code.contains("`json:\"geo,omitempty\"`"));
+    }
+
+    #[test]
+    fn test_root_array() {
+        let value: Value = serde_json::from_str(r#"[{"a": 1}, {"a": 2, "b": true}]"#).unwrap();
+        let code = generate(&value, "ts", "Root").unwrap();
+        assert!(code.contains("export interface RootItem {"));
+        assert!(code.contains("  b?: boolean;"));
+        assert!(code.contains("export type Root = RootItem[];"));
+    }
+
+    #[test]
+    fn test_scalar_root_rejected() {
+        assert!(generate(&Value::from(1), "rust", "Root").is_err());
+        assert!(generate(&sample(), "java", "Root").is_err());
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words("HTTPServer_url2x"), vec!["HTTP", "Server", "url2x"]);
+        assert_eq!(snake_case("firstName"), "first_name");
+        assert_eq!(singular("categories"), "category");
+    }
+}
@@ -0,0 +1,256 @@
+//! RFC 6901 JSON Pointer lookup and editing.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+
+use crate::commands::ExitError;
+
+/// Exit status when a pointer does not resolve
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// How a value given on the command line is interpreted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    /// JSON if it parses, otherwise a plain string
+    Auto,
+    String,
+    Json,
+}
+
+/// Interpret a command-line value according to `ty`
+pub fn parse_value(text: &str, ty: ValueType) -> Result<Value> {
+    match ty {
+        ValueType::String => Ok(Value::String(text.to_string())),
+        ValueType::Json => serde_json::from_str(text).context("Value is not valid JSON"),
+        ValueType::Auto => {
+            Ok(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())))
+        }
+    }
+}
+
+/// Split a pointer into unescaped reference tokens
+pub fn parse(pointer: &str) -> Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        bail!("JSON pointer must be empty or start with '/': {}", pointer);
+    };
+
+    rest.split('/')
+        .map(|token| {
+            let mut out = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    out.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => out.push('~'),
+                    Some('1') => out.push('/'),
+                    _ => bail!("Invalid escape in JSON pointer token: {}", token),
+                }
+            }
+            Ok(out)
+        })
+        .collect()
+}
+
+fn not_found(pointer: &str) -> anyhow::Error {
+    ExitError {
+        code: EXIT_NOT_FOUND,
+        message: format!("Path not found: {}", pointer),
+    }
+    .into()
+}
+
+/// Parse an array index token; `-` and anything malformed yield `None`
+fn index(token: &str) -> Option<usize> {
+    let well_formed =
+        token == "0" || (!token.starts_with('0') && token.bytes().all(|b| b.is_ascii_digit()));
+    if well_formed {
+        token.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn child<'a>(value: &'a Value, token: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => map.get(token),
+        Value::Array(items) => index(token).and_then(|i| items.get(i)),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, token: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Object(map) => map.get_mut(token),
+        Value::Array(items) => index(token).and_then(move |i| items.get_mut(i)),
+        _ => None,
+    }
+}
+
+/// Look up the value a pointer refers to
+pub fn get<'a>(document: &'a Value, pointer: &str) -> Result<&'a Value> {
+    let mut current = document;
+    for token in parse(pointer)? {
+        current = child(current, &token).ok_or_else(|| not_found(pointer))?;
+    }
+    Ok(current)
+}
+
+/// Set the value at a pointer, replacing any existing value
+///
+/// The parent must exist unless `parents` is set, in which case missing
+/// intermediate objects are created. `-` as the last token appends to an array.
+pub fn set(document: &mut Value, pointer: &str, value: Value, parents: bool) -> Result<()> {
+    let tokens = parse(pointer)?;
+    let Some((last, path)) = tokens.split_last() else {
+        *document = value;
+        return Ok(());
+    };
+
+    let mut current = document;
+    for token in path {
+        if parents {
+            if let Value::Object(map) = current {
+                current = map
+                    .entry(token.clone())
+                    .or_insert_with(|| Value::Object(Map::new()));
+                continue;
+            }
+        }
+        current = child_mut(current, token).ok_or_else(|| not_found(pointer))?;
+    }
+
+    match current {
+        Value::Object(map) => {
+            map.insert(last.clone(), value);
+        }
+        Value::Array(items) => {
+            if last == "-" {
+                items.push(value);
+            } else {
+                match index(last) {
+                    Some(i) if i < items.len() => items[i] = value,
+                    Some(i) if i == items.len() => items.push(value),
+                    _ => return Err(not_found(pointer)),
+                }
+            }
+        }
+        other => bail!(
+            "Cannot set a member of a {} value at {}",
+            kind(other),
+            pointer
+        ),
+    }
+    Ok(())
+}
+
+/// Remove the value at a pointer, returning it
+pub fn delete(document: &mut Value, pointer: &str) -> Result<Value> {
+    let tokens = parse(pointer)?;
+    let Some((last, path)) = tokens.split_last() else {
+        bail!("Cannot delete the whole document");
+    };
+
+    let mut current = document;
+    for token in path {
+        current = child_mut(current, token).ok_or_else(|| not_found(pointer))?;
+    }
+
+    let removed = match current {
+        Value::Object(map) => map.shift_remove(last),
+        Value::Array(items) => index(last)
+            .filter(|&i| i < items.len())
+            .map(|i| items.remove(i)),
+        _ => None,
+    };
+    removed.ok_or_else(|| not_found(pointer))
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc() -> Value {
+        json!({
+            "spec": {"replicas": 1, "ports": [80, 443]},
+            "a/b": {"m~n": true},
+            "": "empty key"
+        })
+    }
+
+    #[test]
+    fn test_parse_escapes() {
+        assert_eq!(parse("/a~1b/m~0n").unwrap(), vec!["a/b", "m~n"]);
+        assert!(parse("spec").is_err());
+        assert!(parse("/bad~2").is_err());
+    }
+
+    #[test]
+    fn test_get() {
+        let doc = doc();
+        assert_eq!(get(&doc, "/spec/ports/1").unwrap(), 443);
+        assert_eq!(get(&doc, "/a~1b/m~0n").unwrap(), true);
+        assert_eq!(get(&doc, "/").unwrap(), "empty key");
+        assert_eq!(get(&doc, "").unwrap(), &doc);
+    }
+
+    #[test]
+    fn test_get_missing_has_exit_code() {
+        let err = get(&doc(), "/spec/ports/01").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ExitError>().unwrap().code,
+            EXIT_NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_set() {
+        let mut doc = doc();
+        set(&mut doc, "/spec/replicas", json!(3), false).unwrap();
+        set(&mut doc, "/spec/ports/-", json!(8080), false).unwrap();
+        set(&mut doc, "/spec/ports/0", json!(81), false).unwrap();
+        assert_eq!(
+            doc["spec"],
+            json!({"replicas": 3, "ports": [81, 443, 8080]})
+        );
+
+        assert!(set(&mut doc, "/meta/labels/app", json!("x"), false).is_err());
+        set(&mut doc, "/meta/labels/app", json!("x"), true).unwrap();
+        assert_eq!(doc["meta"]["labels"]["app"], "x");
+        assert!(set(&mut doc, "/spec/ports/9", json!(1), false).is_err());
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut doc = doc();
+        assert_eq!(delete(&mut doc, "/spec/ports/0").unwrap(), 80);
+        assert_eq!(doc["spec"]["ports"], json!([443]));
+        assert!(delete(&mut doc, "/spec/missing").is_err());
+        assert!(delete(&mut doc, "").is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("3", ValueType::Auto).unwrap(), json!(3));
+        assert_eq!(parse_value("web", ValueType::Auto).unwrap(), json!("web"));
+        assert_eq!(parse_value("3", ValueType::String).unwrap(), json!("3"));
+        assert!(parse_value("web", ValueType::Json).is_err());
+    }
+}
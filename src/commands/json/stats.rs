@@ -0,0 +1,429 @@
+//! Shape and size statistics for a JSON document.
+//!
+//! Built on the streaming [`Tokenizer`] so it works on dumps far larger than
+//! memory. Paths use `$` for the root, `.key` for object members and `[n]`
+//! for array elements; in the per-path summaries every index collapses to
+//! `[]` so all elements of an array share one entry.
+
+use super::stream::{Event, SyntaxError, Tokenizer};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt::Write as _;
+use std::io::BufRead;
+
+/// Upper bounds (in characters) of the string length histogram buckets
+const STRING_BUCKETS: [usize; 6] = [0, 16, 64, 256, 1024, 4096];
+
+/// Counts of each JSON type seen at one path
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TypeCounts {
+    pub object: usize,
+    pub array: usize,
+    pub string: usize,
+    pub number: usize,
+    pub boolean: usize,
+    pub null: usize,
+}
+
+impl TypeCounts {
+    fn summary(&self) -> String {
+        [
+            ("object", self.object),
+            ("array", self.array),
+            ("string", self.string),
+            ("number", self.number),
+            ("bool", self.boolean),
+            ("null", self.null),
+        ]
+        .iter()
+        .filter(|(_, n)| *n > 0)
+        .map(|(name, n)| format!("{} {}", name, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// Minimum and maximum of the numbers seen at one path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Range {
+    fn include(range: &mut Option<Range>, n: f64) {
+        match range {
+            Some(r) => {
+                r.min = r.min.min(n);
+                r.max = r.max.max(n);
+            }
+            None => *range = Some(Range { min: n, max: n }),
+        }
+    }
+}
+
+/// Everything `json stats` reports
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub bytes: u64,
+    pub types: TypeCounts,
+    pub keys: usize,
+    pub max_depth: usize,
+    pub deepest_path: String,
+    /// Longest arrays as `(length, path)`, longest first
+    pub largest_arrays: Vec<(usize, String)>,
+    /// Biggest values below the root as `(bytes, path)`, biggest first
+    pub heaviest: Vec<(u64, String)>,
+    /// Number of strings whose length falls in each of `STRING_BUCKETS`,
+    /// plus a final bucket for anything longer
+    pub string_buckets: [usize; 7],
+    pub string_chars: usize,
+    pub longest_string: usize,
+    pub numbers: Option<Range>,
+    pub number_ranges: BTreeMap<String, Range>,
+    pub paths: BTreeMap<String, TypeCounts>,
+}
+
+struct Frame {
+    start: u64,
+    is_array: bool,
+    len: usize,
+    path: String,
+    pattern: String,
+    key: String,
+}
+
+/// Keeps the `n` largest items seen so far
+struct TopN<T: Ord> {
+    n: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopN<T> {
+    fn new(n: usize) -> Self {
+        TopN {
+            n,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        if self.n == 0 {
+            return;
+        }
+        if self.heap.len() < self.n {
+            self.heap.push(Reverse(item));
+        } else if self.heap.peek().is_some_and(|min| item > min.0) {
+            self.heap.pop();
+            self.heap.push(Reverse(item));
+        }
+    }
+
+    fn into_sorted(self) -> Vec<T> {
+        let mut items: Vec<T> = self.heap.into_iter().map(|r| r.0).collect();
+        items.sort_by(|a, b| b.cmp(a));
+        items
+    }
+}
+
+/// Scan a document and gather statistics, keeping the `top` largest arrays
+/// and heaviest subtrees
+pub fn collect<R: BufRead>(reader: R, top: usize) -> Result<Stats, SyntaxError> {
+    let mut tokenizer = Tokenizer::new(reader);
+    let mut stats = Stats::default();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut largest_arrays = TopN::new(top);
+    let mut heaviest = TopN::new(top);
+
+    while let Some(event) = tokenizer.next_event()? {
+        let start = tokenizer.token_start();
+
+        match event {
+            Event::Key(key) => {
+                stats.keys += 1;
+                if let Some(frame) = stack.last_mut() {
+                    frame.key = key;
+                }
+                continue;
+            }
+            Event::EndObject | Event::EndArray => {
+                let frame = stack.pop().expect("tokenizer balances containers");
+                if frame.is_array {
+                    largest_arrays.push((frame.len, frame.path.clone()));
+                }
+                if !stack.is_empty() {
+                    heaviest.push((tokenizer.offset() - frame.start, frame.path));
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        // Everything else starts a new value: work out where it lives
+        let (path, pattern) = match stack.last_mut() {
+            None => ("$".to_string(), "$".to_string()),
+            Some(parent) if parent.is_array => {
+                let index = parent.len;
+                parent.len += 1;
+                (
+                    format!("{}[{}]", parent.path, index),
+                    format!("{}[]", parent.pattern),
+                )
+            }
+            Some(parent) => {
+                parent.len += 1;
+                let member = member_path(&parent.key);
+                (
+                    format!("{}{}", parent.path, member),
+                    format!("{}{}", parent.pattern, member),
+                )
+            }
+        };
+        let counts = stats.paths.entry(pattern.clone()).or_default();
+
+        match event {
+            Event::StartObject | Event::StartArray => {
+                let is_array = event == Event::StartArray;
+                if is_array {
+                    counts.array += 1;
+                    stats.types.array += 1;
+                } else {
+                    counts.object += 1;
+                    stats.types.object += 1;
+                }
+                stack.push(Frame {
+                    start,
+                    is_array,
+                    len: 0,
+                    path,
+                    pattern,
+                    key: String::new(),
+                });
+                if stack.len() > stats.max_depth {
+                    stats.max_depth = stack.len();
+                    stats.deepest_path = stack.last().unwrap().path.clone();
+                }
+                continue;
+            }
+            Event::String(s) => {
+                counts.string += 1;
+                stats.types.string += 1;
+                let chars = s.chars().count();
+                let bucket = STRING_BUCKETS
+                    .iter()
+                    .position(|&limit| chars <= limit)
+                    .unwrap_or(STRING_BUCKETS.len());
+                stats.string_buckets[bucket] += 1;
+                stats.string_chars += chars;
+                stats.longest_string = stats.longest_string.max(chars);
+            }
+            Event::Number(n) => {
+                counts.number += 1;
+                stats.types.number += 1;
+                if let Ok(n) = n.parse::<f64>() {
+                    Range::include(&mut stats.numbers, n);
+                    let mut range = stats.number_ranges.get(&pattern).copied();
+                    Range::include(&mut range, n);
+                    stats.number_ranges.insert(pattern, range.unwrap());
+                }
+            }
+            Event::Bool(_) => {
+                counts.boolean += 1;
+                stats.types.boolean += 1;
+            }
+            Event::Null => {
+                counts.null += 1;
+                stats.types.null += 1;
+            }
+            Event::Key(_) | Event::EndObject | Event::EndArray => unreachable!(),
+        }
+
+        if !stack.is_empty() {
+            heaviest.push((tokenizer.offset() - start, path));
+        }
+    }
+
+    stats.bytes = tokenizer.offset();
+    stats.largest_arrays = largest_arrays.into_sorted();
+    stats.heaviest = heaviest.into_sorted();
+    Ok(stats)
+}
+
+/// Path suffix for an object member: `.key`, or `["key"]` when the key is
+/// not a plain identifier
+pub(crate) fn member_path(key: &str) -> String {
+    let plain = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        format!(".{}", key)
+    } else {
+        format!("[{}]", serde_json::to_string(key).unwrap_or_default())
+    }
+}
+
+impl Stats {
+    /// Render the statistics as a human-readable report
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let t = &self.types;
+        let values = t.object + t.array + t.string + t.number + t.boolean + t.null;
+
+        let _ = writeln!(out, "Size:       {}", human_bytes(self.bytes));
+        let _ = writeln!(out, "Values:     {} ({})", values, t.summary());
+        let _ = writeln!(out, "Keys:       {}", self.keys);
+        // A scalar document has no container to point at
+        if self.max_depth == 0 || self.deepest_path.is_empty() {
+            let _ = writeln!(out, "Max depth:  {}", self.max_depth);
+        } else {
+            let _ = writeln!(
+                out,
+                "Max depth:  {} (at {})",
+                self.max_depth, self.deepest_path
+            );
+        }
+
+        if !self.largest_arrays.is_empty() {
+            let _ = writeln!(out, "\nLargest arrays:");
+            for (len, path) in &self.largest_arrays {
+                let _ = writeln!(out, "  {:>10} items  {}", len, path);
+            }
+        }
+
+        if !self.heaviest.is_empty() {
+            let _ = writeln!(out, "\nHeaviest subtrees:");
+            for (bytes, path) in &self.heaviest {
+                let share = *bytes as f64 * 100.0 / self.bytes.max(1) as f64;
+                let _ = writeln!(
+                    out,
+                    "  {:>10}  {:>5.1}%  {}",
+                    human_bytes(*bytes),
+                    share,
+                    path
+                );
+            }
+        }
+
+        if t.string > 0 {
+            let _ = writeln!(
+                out,
+                "\nStrings:    {} (mean {:.1} chars, longest {})",
+                t.string,
+                self.string_chars as f64 / t.string as f64,
+                self.longest_string
+            );
+            let mut lower = 0;
+            for (i, count) in self.string_buckets.iter().enumerate() {
+                let label = match STRING_BUCKETS.get(i) {
+                    Some(0) => "empty".to_string(),
+                    Some(&upper) => format!("{}-{}", lower, upper),
+                    None => format!("> {}", lower - 1),
+                };
+                if let Some(&upper) = STRING_BUCKETS.get(i) {
+                    lower = upper + 1;
+                }
+                if *count > 0 {
+                    let _ = writeln!(out, "  {:>12} chars  {}", label, count);
+                }
+            }
+        }
+
+        if let Some(range) = self.numbers {
+            let _ = writeln!(
+                out,
+                "\nNumbers:    {} (min {}, max {})",
+                t.number, range.min, range.max
+            );
+            for (path, range) in &self.number_ranges {
+                let _ = writeln!(out, "  {}  [{} .. {}]", path, range.min, range.max);
+            }
+        }
+
+        let _ = writeln!(out, "\nTypes by path:");
+        for (path, counts) in &self.paths {
+            let _ = writeln!(out, "  {}  {}", path, counts.summary());
+        }
+
+        out.trim_end().to_string()
+    }
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "users": [
+            {"name": "ann", "age": 31, "tags": ["a", "b", "c"]},
+            {"name": "bob", "age": 42.5, "tags": [], "email": null}
+        ],
+        "meta": {"total": 2, "weird key": true}
+    }"#;
+
+    #[test]
+    fn test_counts_and_depth() {
+        let stats = collect(SAMPLE.as_bytes(), 3).unwrap();
+        assert_eq!(stats.types.object, 4);
+        assert_eq!(stats.types.array, 3);
+        assert_eq!(stats.keys, 11);
+        assert_eq!(stats.max_depth, 4);
+        assert_eq!(stats.deepest_path, "$.users[0].tags");
+        assert_eq!(stats.bytes, SAMPLE.len() as u64);
+    }
+
+    #[test]
+    fn test_scalar_root() {
+        let rendered = collect("42".as_bytes(), 3).unwrap().render();
+        assert!(rendered.contains("Max depth:  0\n"), "{}", rendered);
+        assert!(!rendered.contains("(at"));
+    }
+
+    #[test]
+    fn test_largest_arrays() {
+        let stats = collect(SAMPLE.as_bytes(), 2).unwrap();
+        assert_eq!(
+            stats.largest_arrays,
+            vec![
+                (3, "$.users[0].tags".to_string()),
+                (2, "$.users".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heaviest_excludes_root() {
+        let stats = collect(SAMPLE.as_bytes(), 1).unwrap();
+        assert_eq!(stats.heaviest.len(), 1);
+        assert_eq!(stats.heaviest[0].1, "$.users");
+    }
+
+    #[test]
+    fn test_paths_and_ranges() {
+        let stats = collect(SAMPLE.as_bytes(), 0).unwrap();
+        let email = &stats.paths["$.users[].email"];
+        assert_eq!(email.null, 1);
+        assert!(stats.paths.contains_key(r#"$.meta["weird key"]"#));
+        assert_eq!(
+            stats.number_ranges["$.users[].age"],
+            Range {
+                min: 31.0,
+                max: 42.5
+            }
+        );
+        assert_eq!(stats.string_buckets[1], 5);
+    }
+}
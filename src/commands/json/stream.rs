@@ -0,0 +1,560 @@
+//! Streaming JSON tokenizer.
+//!
+//! Pulls events out of any `BufRead` one token at a time, so `minify`,
+//! `validate` and friends can process multi-gigabyte documents without
+//! building a `serde_json::Value` for the whole thing. Memory use is bounded
+//! by nesting depth plus the size of the largest single string or number.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// A single token produced by the [`Tokenizer`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// An object key (already unescaped)
+    Key(String),
+    /// A string value (already unescaped)
+    String(String),
+    /// A number value, exactly as written in the source
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+/// Syntax or I/O error with the position it occurred at
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Expecting any value
+    Value,
+    /// Just after `[`: a value or `]`
+    ArrayFirst,
+    /// Just after `{`: a key or `}`
+    ObjectFirst,
+    /// Just after `,` in an object: a key
+    ObjectKey,
+    /// Just after a key: `:`
+    Colon,
+    /// After a complete value: `,`, a closing bracket, or end of input
+    AfterValue,
+    /// End of input reached
+    Done,
+}
+
+/// Pull-based JSON tokenizer over a buffered reader
+pub struct Tokenizer<R> {
+    reader: R,
+    stack: Vec<Container>,
+    state: State,
+    line: usize,
+    column: usize,
+    offset: u64,
+    token_start: u64,
+}
+
+impl<R: BufRead> Tokenizer<R> {
+    pub fn new(reader: R) -> Self {
+        Tokenizer {
+            reader,
+            stack: Vec::new(),
+            state: State::Value,
+            line: 1,
+            column: 0,
+            offset: 0,
+            token_start: 0,
+        }
+    }
+
+    /// Current nesting depth (number of open objects and arrays)
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Byte offset just past the last consumed byte
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Byte offset of the first byte of the last returned event
+    pub fn token_start(&self) -> u64 {
+        self.token_start
+    }
+
+    /// Return the next event, or `None` once the document is complete
+    pub fn next_event(&mut self) -> Result<Option<Event>, SyntaxError> {
+        loop {
+            self.skip_whitespace()?;
+            self.token_start = self.offset;
+            let byte = self.peek()?;
+
+            match self.state {
+                State::Done => return Ok(None),
+                State::Value => return self.parse_value(byte).map(Some),
+                State::ArrayFirst => {
+                    if byte == Some(b']') {
+                        self.bump()?;
+                        return Ok(Some(self.close(Container::Array)));
+                    }
+                    return self.parse_value(byte).map(Some);
+                }
+                State::ObjectFirst | State::ObjectKey => match byte {
+                    Some(b'}') if self.state == State::ObjectFirst => {
+                        self.bump()?;
+                        return Ok(Some(self.close(Container::Object)));
+                    }
+                    Some(b'"') => {
+                        self.bump()?;
+                        let key = self.parse_string()?;
+                        self.state = State::Colon;
+                        return Ok(Some(Event::Key(key)));
+                    }
+                    None => return Err(self.error("EOF while parsing an object")),
+                    Some(_) => return Err(self.error("key must be a string")),
+                },
+                State::Colon => match byte {
+                    Some(b':') => {
+                        self.bump()?;
+                        self.state = State::Value;
+                    }
+                    None => return Err(self.error("EOF while parsing an object")),
+                    Some(_) => return Err(self.error("expected `:`")),
+                },
+                State::AfterValue => match (self.stack.last().copied(), byte) {
+                    (None, None) => {
+                        self.state = State::Done;
+                        return Ok(None);
+                    }
+                    (None, Some(_)) => return Err(self.error("trailing characters")),
+                    (Some(Container::Array), Some(b',')) => {
+                        self.bump()?;
+                        self.state = State::Value;
+                    }
+                    (Some(Container::Array), Some(b']')) => {
+                        self.bump()?;
+                        return Ok(Some(self.close(Container::Array)));
+                    }
+                    (Some(Container::Array), None) => {
+                        return Err(self.error("EOF while parsing a list"))
+                    }
+                    (Some(Container::Array), Some(_)) => {
+                        return Err(self.error("expected `,` or `]`"))
+                    }
+                    (Some(Container::Object), Some(b',')) => {
+                        self.bump()?;
+                        self.state = State::ObjectKey;
+                    }
+                    (Some(Container::Object), Some(b'}')) => {
+                        self.bump()?;
+                        return Ok(Some(self.close(Container::Object)));
+                    }
+                    (Some(Container::Object), None) => {
+                        return Err(self.error("EOF while parsing an object"))
+                    }
+                    (Some(Container::Object), Some(_)) => {
+                        return Err(self.error("expected `,` or `}`"))
+                    }
+                },
+            }
+        }
+    }
+
+    fn close(&mut self, container: Container) -> Event {
+        self.stack.pop();
+        self.state = State::AfterValue;
+        match container {
+            Container::Object => Event::EndObject,
+            Container::Array => Event::EndArray,
+        }
+    }
+
+    fn parse_value(&mut self, byte: Option<u8>) -> Result<Event, SyntaxError> {
+        let event = match byte {
+            Some(b'{') => {
+                self.bump()?;
+                self.stack.push(Container::Object);
+                self.state = State::ObjectFirst;
+                return Ok(Event::StartObject);
+            }
+            Some(b'[') => {
+                self.bump()?;
+                self.stack.push(Container::Array);
+                self.state = State::ArrayFirst;
+                return Ok(Event::StartArray);
+            }
+            Some(b'"') => {
+                self.bump()?;
+                Event::String(self.parse_string()?)
+            }
+            Some(b't') => {
+                self.expect_literal(b"true")?;
+                Event::Bool(true)
+            }
+            Some(b'f') => {
+                self.expect_literal(b"false")?;
+                Event::Bool(false)
+            }
+            Some(b'n') => {
+                self.expect_literal(b"null")?;
+                Event::Null
+            }
+            Some(b'-' | b'0'..=b'9') => Event::Number(self.parse_number()?),
+            None => return Err(self.error("EOF while parsing a value")),
+            Some(_) => return Err(self.error("expected value")),
+        };
+        self.state = State::AfterValue;
+        Ok(event)
+    }
+
+    fn expect_literal(&mut self, literal: &[u8]) -> Result<(), SyntaxError> {
+        for &expected in literal {
+            match self.peek()? {
+                Some(b) if b == expected => self.bump()?,
+                None => return Err(self.error("EOF while parsing a value")),
+                Some(_) => return Err(self.error("expected ident")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_number(&mut self) -> Result<String, SyntaxError> {
+        let mut text = String::new();
+
+        if self.peek()? == Some(b'-') {
+            text.push('-');
+            self.bump()?;
+        }
+
+        match self.peek()? {
+            Some(b'0') => {
+                text.push('0');
+                self.bump()?;
+            }
+            Some(b'1'..=b'9') => self.take_digits(&mut text)?,
+            _ => return Err(self.error("invalid number")),
+        }
+
+        if self.peek()? == Some(b'.') {
+            text.push('.');
+            self.bump()?;
+            if !matches!(self.peek()?, Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.take_digits(&mut text)?;
+        }
+
+        if let Some(e @ (b'e' | b'E')) = self.peek()? {
+            text.push(e as char);
+            self.bump()?;
+            if let Some(sign @ (b'+' | b'-')) = self.peek()? {
+                text.push(sign as char);
+                self.bump()?;
+            }
+            if !matches!(self.peek()?, Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.take_digits(&mut text)?;
+        }
+
+        Ok(text)
+    }
+
+    fn take_digits(&mut self, text: &mut String) -> Result<(), SyntaxError> {
+        while let Some(b @ b'0'..=b'9') = self.peek()? {
+            text.push(b as char);
+            self.bump()?;
+        }
+        Ok(())
+    }
+
+    /// Parse a string body; the opening quote has already been consumed
+    fn parse_string(&mut self) -> Result<String, SyntaxError> {
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.peek()? {
+                None => return Err(self.error("EOF while parsing a string")),
+                Some(b'"') => {
+                    self.bump()?;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.bump()?;
+                    self.parse_escape(&mut bytes)?;
+                }
+                Some(b) if b < 0x20 => {
+                    return Err(self
+                        .error("control character (\\u0000-\\u001F) found while parsing a string"))
+                }
+                Some(b) => {
+                    bytes.push(b);
+                    self.bump()?;
+                }
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error("invalid unicode in string"))
+    }
+
+    fn parse_escape(&mut self, bytes: &mut Vec<u8>) -> Result<(), SyntaxError> {
+        let escaped = match self.peek()? {
+            Some(b) => b,
+            None => return Err(self.error("EOF while parsing a string")),
+        };
+        self.bump()?;
+
+        let decoded = match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.parse_hex4()?;
+                let code = if (0xD800..0xDC00).contains(&high) {
+                    if self.peek()? != Some(b'\\') {
+                        return Err(self.error("lone leading surrogate in hex escape"));
+                    }
+                    self.bump()?;
+                    if self.peek()? != Some(b'u') {
+                        return Err(self.error("lone leading surrogate in hex escape"));
+                    }
+                    self.bump()?;
+                    let low = self.parse_hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("lone leading surrogate in hex escape"));
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or_else(|| self.error("invalid unicode code point"))?
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+
+        let mut buf = [0; 4];
+        bytes.extend_from_slice(decoded.encode_utf8(&mut buf).as_bytes());
+        Ok(())
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, SyntaxError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = match self.peek()? {
+                Some(b) => (b as char).to_digit(16),
+                None => return Err(self.error("EOF while parsing a string")),
+            };
+            match digit {
+                Some(d) => value = value * 16 + d,
+                None => return Err(self.error("invalid escape")),
+            }
+            self.bump()?;
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), SyntaxError> {
+        while let Some(b' ' | b'\n' | b'\r' | b'\t') = self.peek()? {
+            self.bump()?;
+        }
+        Ok(())
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, SyntaxError> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => return Ok(buf.first().copied()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(self.error(&format!("I/O error: {}", e))),
+            }
+        }
+    }
+
+    /// Consume the byte returned by the last `peek`
+    fn bump(&mut self) -> Result<(), SyntaxError> {
+        if let Some(b) = self.peek()? {
+            self.reader.consume(1);
+            self.offset += 1;
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 0;
+            } else if b & 0xC0 != 0x80 {
+                self.column += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError {
+            message: message.to_string(),
+            line: self.line,
+            column: self.column + 1,
+        }
+    }
+}
+
+/// Structure counts gathered while scanning a document
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Counts {
+    pub objects: usize,
+    pub arrays: usize,
+    pub keys: usize,
+}
+
+/// Validate a document and count its objects, arrays and keys
+pub fn scan<R: BufRead>(reader: R) -> Result<Counts, SyntaxError> {
+    let mut tokenizer = Tokenizer::new(reader);
+    let mut counts = Counts::default();
+
+    while let Some(event) = tokenizer.next_event()? {
+        match event {
+            Event::StartObject => counts.objects += 1,
+            Event::StartArray => counts.arrays += 1,
+            Event::Key(_) => counts.keys += 1,
+            _ => {}
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Write the minified form of a document to `writer`.
+///
+/// Keys keep their source order and numbers are copied verbatim; strings are
+/// re-escaped the same way `serde_json` does.
+pub fn minify<R: BufRead, W: Write>(reader: R, writer: &mut W) -> anyhow::Result<()> {
+    let mut tokenizer = Tokenizer::new(reader);
+    // Whether the next value in the current container needs a leading comma
+    let mut needs_comma = false;
+
+    while let Some(event) = tokenizer.next_event()? {
+        if needs_comma && !matches!(event, Event::EndObject | Event::EndArray) {
+            writer.write_all(b",")?;
+        }
+
+        match &event {
+            Event::StartObject => writer.write_all(b"{")?,
+            Event::EndObject => writer.write_all(b"}")?,
+            Event::StartArray => writer.write_all(b"[")?,
+            Event::EndArray => writer.write_all(b"]")?,
+            Event::Key(key) => {
+                write_escaped(writer, key)?;
+                writer.write_all(b":")?;
+            }
+            Event::String(s) => write_escaped(writer, s)?,
+            Event::Number(n) => writer.write_all(n.as_bytes())?,
+            Event::Bool(true) => writer.write_all(b"true")?,
+            Event::Bool(false) => writer.write_all(b"false")?,
+            Event::Null => writer.write_all(b"null")?,
+        }
+
+        needs_comma = !matches!(
+            event,
+            Event::StartObject | Event::StartArray | Event::Key(_)
+        );
+    }
+
+    Ok(())
+}
+
+fn write_escaped<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, s).map_err(io::Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minify_str(input: &str) -> String {
+        let mut out = Vec::new();
+        minify(input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_minify_preserves_order() {
+        let input = "{ \"b\" : [1, 2.5e3, -0] ,\n \"a\": {\"x\": null, \"y\": [] } }";
+        assert_eq!(
+            minify_str(input),
+            r#"{"b":[1,2.5e3,-0],"a":{"x":null,"y":[]}}"#
+        );
+    }
+
+    #[test]
+    fn test_minify_escapes() {
+        let input = r#"["\u0041\n\"\ud83d\ude80", "tab\there"]"#;
+        assert_eq!(minify_str(input), r#"["A\n\"🚀","tab\there"]"#);
+    }
+
+    #[test]
+    fn test_scan_counts() {
+        let input = r#"{"a": [{"b": 1}, {"c": 2, "d": [3]}]}"#;
+        let counts = scan(input.as_bytes()).unwrap();
+        assert_eq!(
+            counts,
+            Counts {
+                objects: 3,
+                arrays: 2,
+                keys: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_scan_errors() {
+        for input in [
+            "",
+            "{",
+            "[1,]",
+            r#"{"a" 1}"#,
+            r#"{"a":1,}"#,
+            "01",
+            "1.",
+            "tru",
+            "[1] 2",
+            "\"unterminated",
+            "\"\\ud800\"",
+        ] {
+            assert!(scan(input.as_bytes()).is_err(), "accepted {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_error_position() {
+        let err = scan("{\n  \"a\": }".as_bytes()).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.column, 8);
+    }
+}
@@ -1,6 +1,42 @@
 pub mod base64;
+pub mod convert;
+pub mod csv;
+pub mod escape;
 pub mod hash;
+pub mod input;
 pub mod json;
 pub mod time;
 pub mod url;
 pub mod uuid;
+
+use std::fmt;
+
+/// Error that makes the CLI exit with a specific status instead of 1
+#[derive(Debug)]
+pub struct ExitError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
+
+/// What a command produced
+#[derive(Debug, PartialEq)]
+pub enum Output {
+    /// Text for the caller to print
+    Text(String),
+    /// Already streamed to stdout
+    Written,
+}
+
+impl From<String> for Output {
+    fn from(text: String) -> Self {
+        Output::Text(text)
+    }
+}
@@ -1,98 +1,441 @@
-use anyhow::{Context, Result};
-use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc};
+use chrono_tz::Tz;
+use std::io::{self, Write};
+use std::path::Path;
 
-/// Show current time as Unix timestamp
-pub fn now() -> Result<String> {
+use super::input::{open_input, Source};
+use super::Output;
+
+mod annotate;
+mod calendar;
+mod cron;
+mod duration;
+mod format;
+mod id;
+mod parse;
+
+/// Look up an IANA time zone such as `America/New_York`
+pub fn parse_zone(name: &str) -> Result<Tz> {
+    name.trim().parse().map_err(|_| {
+        anyhow!(
+            "Unknown time zone: {}. Use an IANA name like America/New_York or UTC.",
+            name
+        )
+    })
+}
+
+/// Parse a comma-separated list of zones for `--show-tz`
+fn parse_zones(list: Option<&str>) -> Result<Vec<Tz>> {
+    list.map_or(Ok(Vec::new()), |list| {
+        list.split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(parse_zone)
+            .collect()
+    })
+}
+
+/// One line per zone showing the wall-clock time and offset of `dt`
+fn zone_lines(dt: DateTime<Utc>, zones: &[Tz]) -> String {
+    let width = zones.iter().map(|tz| tz.name().len()).max().unwrap_or(0);
+    zones
+        .iter()
+        .map(|tz| {
+            format!(
+                "\n{:<width$} {}",
+                format!("{}:", tz.name()),
+                dt.with_timezone(tz).format("%Y-%m-%d %H:%M:%S %Z (%:z)"),
+                width = width + 1
+            )
+        })
+        .collect()
+}
+
+/// Show current time as Unix timestamp, plus the time in each `show_tz` zone
+///
+/// With `format` (a preset or `strftime` pattern) only that UTC value is printed.
+pub fn now(show_tz: Option<&str>, format: Option<&str>) -> Result<String> {
+    let zones = parse_zones(show_tz)?;
     let now = Utc::now();
+    if let Some(format) = format {
+        return format::render(now.with_timezone(&Tz::UTC), format);
+    }
     let local = Local::now();
     
     Ok(format!(
         "Unix timestamp: {}\n\
          UTC:   {}\n\
-         Local: {}",
+         Local: {}{}",
         now.timestamp(),
         now.format("%Y-%m-%d %H:%M:%S UTC"),
-        local.format("%Y-%m-%d %H:%M:%S %Z")
+        local.format("%Y-%m-%d %H:%M:%S %Z"),
+        zone_lines(now, &zones)
     ))
 }
 
-/// Convert Unix timestamp to human-readable
-pub fn from_unix(timestamp: i64) -> Result<String> {
-    // Handle both seconds and milliseconds
-    let (secs, millis) = if timestamp > 10_000_000_000 {
-        // Likely milliseconds
-        (timestamp / 1000, timestamp % 1000)
-    } else {
-        (timestamp, 0)
+/// Resolution of a Unix timestamp
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl Unit {
+    pub fn parse(name: &str) -> Result<Unit> {
+        match name.to_lowercase().as_str() {
+            "s" | "sec" | "seconds" => Ok(Unit::Seconds),
+            "ms" | "millis" | "milliseconds" => Ok(Unit::Millis),
+            "us" | "µs" | "micros" | "microseconds" => Ok(Unit::Micros),
+            "ns" | "nanos" | "nanoseconds" => Ok(Unit::Nanos),
+            _ => anyhow::bail!("Unknown unit: {}. Use s, ms, us, or ns.", name),
+        }
+    }
+
+    /// Guess the unit from the magnitude: seconds up to 1e11 (year 5138),
+    /// then milliseconds, microseconds and nanoseconds by factors of 1000
+    pub fn detect(timestamp: i64) -> Unit {
+        match timestamp.unsigned_abs() {
+            0..=99_999_999_999 => Unit::Seconds,
+            100_000_000_000..=99_999_999_999_999 => Unit::Millis,
+            100_000_000_000_000..=99_999_999_999_999_999 => Unit::Micros,
+            _ => Unit::Nanos,
+        }
+    }
+
+    fn per_second(self) -> i64 {
+        match self {
+            Unit::Seconds => 1,
+            Unit::Millis => 1_000,
+            Unit::Micros => 1_000_000,
+            Unit::Nanos => 1_000_000_000,
+        }
+    }
+
+    /// The instant `timestamp` in this unit refers to
+    pub fn to_datetime(self, timestamp: i64) -> Option<DateTime<Utc>> {
+        // Euclidean division keeps the sub-second part positive before 1970
+        let per_second = self.per_second();
+        let secs = timestamp.div_euclid(per_second);
+        let nanos = timestamp.rem_euclid(per_second) * (1_000_000_000 / per_second);
+        DateTime::from_timestamp(secs, nanos as u32)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Unit::Seconds => "seconds",
+            Unit::Millis => "milliseconds",
+            Unit::Micros => "microseconds",
+            Unit::Nanos => "nanoseconds",
+        }
+    }
+}
+
+/// Convert Unix timestamp to human-readable, plus the time in each `show_tz` zone
+///
+/// Without an explicit `unit` it is guessed from the magnitude, which works
+/// for negative (pre-1970) values too. With `format` only that UTC value is
+/// printed.
+pub fn from_unix(
+    timestamp: i64,
+    unit: Option<&str>,
+    show_tz: Option<&str>,
+    format: Option<&str>,
+) -> Result<String> {
+    let zones = parse_zones(show_tz)?;
+    let (unit, how) = match unit {
+        Some(name) => (Unit::parse(name)?, "given"),
+        None => (Unit::detect(timestamp), "auto-detected"),
     };
 
-    let dt = DateTime::from_timestamp(secs, (millis * 1_000_000) as u32)
-        .context("Invalid timestamp")?;
-    
+    let dt = unit.to_datetime(timestamp).context("Invalid timestamp")?;
+    if let Some(format) = format {
+        return format::render(dt.with_timezone(&Tz::UTC), format);
+    }
+
+    Ok(describe_unix(format!("Unit:  {} ({})", unit.name(), how), dt, &zones))
+}
+
+/// Decode the creation time embedded in a snowflake, ULID, KSUID, MongoDB
+/// ObjectId or UUID v1/v6/v7; `kind` overrides detection
+pub fn from_id(
+    id: &str,
+    kind: Option<&str>,
+    show_tz: Option<&str>,
+    format: Option<&str>,
+) -> Result<String> {
+    let zones = parse_zones(show_tz)?;
+    let kind = kind.map(id::Kind::parse).transpose()?;
+    let decoded = id::decode(id, kind)?;
+    if let Some(format) = format {
+        return format::render(decoded.instant.with_timezone(&Tz::UTC), format);
+    }
+
+    let mut header = format!("Type:  {}", decoded.name);
+    if let Some(details) = decoded.details {
+        header.push_str(&format!("\nParts: {}", details));
+    }
+    let mut out = describe_unix(header, decoded.instant, &zones);
+    if let Some(note) = decoded.note {
+        out.push_str(&format!("\n\nNote: {}", note));
+    }
+    Ok(out)
+}
+
+/// Output shared by `from_unix` and `from_id`, below a first line that says
+/// how the timestamp was read
+fn describe_unix(header: String, dt: DateTime<Utc>, zones: &[Tz]) -> String {
     let local: DateTime<Local> = dt.into();
-    
-    Ok(format!(
-        "UTC:   {}\n\
+
+    format!(
+        "{}\n\
+         UTC:   {}\n\
          Local: {}\n\
-         ISO:   {}",
-        dt.format("%Y-%m-%d %H:%M:%S UTC"),
-        local.format("%Y-%m-%d %H:%M:%S %Z"),
-        dt.to_rfc3339()
-    ))
+         ISO:   {}{}",
+        header,
+        dt.format("%Y-%m-%d %H:%M:%S%.f UTC"),
+        local.format("%Y-%m-%d %H:%M:%S%.f %Z"),
+        dt.to_rfc3339(),
+        zone_lines(dt, zones)
+    )
+}
+
+/// Parse an absolute or relative date, reading wall-clock input in `zone`
+fn parse_date(date: &str, zone: Tz) -> Result<parse::Parsed> {
+    parse::parse(date, Utc::now(), zone).ok_or_else(|| {
+        anyhow!(
+            "Could not parse date: {}. Try formats like:\n\
+             • 2024-12-28 15:30:00\n\
+             • 2024-12-28T15:30:00Z (ISO 8601)\n\
+             • Sat, 28 Dec 2024 15:30:00 +0000 (RFC 2822 / HTTP)\n\
+             • 28/Dec/2024:15:30:00 +0000 (log format)\n\
+             • now, yesterday, +3d, 2 hours ago, next monday 09:00",
+            date
+        )
+    })
+}
+
+fn parse_duration(text: &str) -> Result<duration::Span> {
+    duration::parse(text).ok_or_else(|| {
+        anyhow!(
+            "Could not parse duration: {}. Try 90d, 1h30m, \"2 hours 5 minutes\", 3725 or PT1H2M5S",
+            text
+        )
+    })
 }
 
 /// Convert human-readable date to Unix timestamp
-pub fn to_unix(date: &str) -> Result<String> {
-    // Try various formats
-    let formats = [
-        "%Y-%m-%d %H:%M:%S",
-        "%Y-%m-%d %H:%M",
-        "%Y-%m-%d",
-        "%Y/%m/%d %H:%M:%S",
-        "%Y/%m/%d",
-        "%d-%m-%Y %H:%M:%S",
-        "%d/%m/%Y %H:%M:%S",
-    ];
-
-    for fmt in &formats {
-        if let Ok(naive) = NaiveDateTime::parse_from_str(date, fmt) {
-            let dt = Utc.from_utc_datetime(&naive);
-            return Ok(format!(
-                "Unix timestamp: {}\n\
-                 Milliseconds:   {}",
-                dt.timestamp(),
-                dt.timestamp_millis()
-            ));
-        }
-        
-        // Try date-only formats
-        if let Ok(naive_date) = chrono::NaiveDate::parse_from_str(date, fmt) {
-            let naive = naive_date.and_hms_opt(0, 0, 0).unwrap();
-            let dt = Utc.from_utc_datetime(&naive);
-            return Ok(format!(
-                "Unix timestamp: {}\n\
-                 Milliseconds:   {}",
-                dt.timestamp(),
-                dt.timestamp_millis()
-            ));
+///
+/// Accepts absolute dates (RFC 3339, RFC 2822, HTTP-date, log timestamps)
+/// and relative expressions like "2 hours ago" or "next monday 09:00".
+/// Input without an offset is read in `tz` (UTC by default); times that fall
+/// in a DST fold or gap are resolved with a note. With `format` only that
+/// value is printed, in `tz`, and the note goes to stderr.
+pub fn to_unix(
+    date: &str,
+    tz: Option<&str>,
+    show_tz: Option<&str>,
+    format: Option<&str>,
+) -> Result<String> {
+    let zone = tz.map(parse_zone).transpose()?;
+    let parsed = parse_date(date, zone.unwrap_or(Tz::UTC))?;
+    describe_instant(parsed.instant, parsed.note, zone, show_tz, format)
+}
+
+/// Add a duration to a date, e.g. "now" + "90d"
+///
+/// Months and years follow the calendar; days are always 24 hours.
+pub fn add(
+    date: &str,
+    duration: &str,
+    tz: Option<&str>,
+    show_tz: Option<&str>,
+    format: Option<&str>,
+) -> Result<String> {
+    let zone = tz.map(parse_zone).transpose()?;
+    let parsed = parse_date(date, zone.unwrap_or(Tz::UTC))?;
+    let span = parse_duration(duration)?;
+    let moved = span
+        .apply(parsed.instant, zone.unwrap_or(Tz::UTC))
+        .context("Resulting date is out of range")?;
+    let note = match (parsed.note, moved.note) {
+        (Some(a), Some(b)) => Some(format!("{}\nNote: {}", a, b)),
+        (a, b) => a.or(b),
+    };
+    describe_instant(moved.instant, note, zone, show_tz, format)
+}
+
+/// Time elapsed from `from` to `to` (negative if `to` is earlier)
+pub fn diff(from: &str, to: &str, tz: Option<&str>) -> Result<String> {
+    let zone = tz.map(parse_zone).transpose()?.unwrap_or(Tz::UTC);
+    let start = parse_date(from, zone)?;
+    let end = parse_date(to, zone)?;
+
+    let notes: String = [start.note, end.note]
+        .into_iter()
+        .flatten()
+        .map(|note| format!("\nNote: {}", note))
+        .collect();
+    Ok(describe_duration(end.instant - start.instant) + &notes)
+}
+
+/// Normalize a duration into human-readable and ISO 8601 forms
+pub fn duration(text: &str) -> Result<String> {
+    let span = parse_duration(text)?;
+    if span.months != 0 {
+        anyhow::bail!(
+            "Months and years have no fixed length; use `time add` with a date instead"
+        );
+    }
+    Ok(describe_duration(span.exact))
+}
+
+/// Explain a cron expression and list its next `count` runs in `tz`
+pub fn cron(
+    expression: &str,
+    count: usize,
+    tz: Option<&str>,
+    format: Option<&str>,
+) -> Result<String> {
+    let zone = tz.map(parse_zone).transpose()?.unwrap_or(Tz::UTC);
+    let schedule = cron::Schedule::parse(expression)?;
+
+    let mut out = format!(
+        "Expression: {}\n\
+         Meaning:    {}\n\
+         Time zone:  {}",
+        expression.trim(),
+        schedule.explain(),
+        zone
+    );
+    if count == 0 {
+        return Ok(out);
+    }
+
+    let runs = schedule.upcoming(Utc::now().with_timezone(&zone), count);
+    if runs.is_empty() {
+        out.push_str("\nNo upcoming runs");
+    } else {
+        out.push_str(&format!("\nNext {} runs:", runs.len()));
+    }
+    for run in runs {
+        let text = match format {
+            Some(format) => format::render(run, format)?,
+            None => run.format("%Y-%m-%d %H:%M:%S %Z (%a)").to_string(),
+        };
+        out.push_str(&format!("\n  {}", text));
+    }
+    Ok(out)
+}
+
+/// Annotate epoch and ISO timestamps in a log with their time in `tz`, or
+/// replace them with `replace`, streaming `path` (stdin for `-`) to stdout
+pub fn annotate(
+    path: &str,
+    tz: Option<&str>,
+    replace: bool,
+    format: Option<&str>,
+) -> Result<Output> {
+    let zone = tz.map(parse_zone).transpose()?.unwrap_or(Tz::UTC);
+    let input = if path == "-" {
+        Source::Stdin
+    } else {
+        Source::File(Path::new(path))
+    };
+    let reader = open_input(input)?;
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    annotate::annotate(
+        reader,
+        &mut out,
+        zone,
+        replace,
+        format.unwrap_or("%Y-%m-%d %H:%M:%S %Z"),
+    )?;
+    out.flush()?;
+    Ok(Output::Written)
+}
+
+/// ISO week, day of year, quarter and other calendar facts for a date
+pub fn info(date: &str, tz: Option<&str>) -> Result<String> {
+    let zone = tz.map(parse_zone).transpose()?.unwrap_or(Tz::UTC);
+    let parsed = parse_date(date, zone)?;
+    let day = parsed.instant.with_timezone(&zone).date_naive();
+
+    let mut out = format!(
+        "{}\nUnix time:     {}",
+        calendar::info(day),
+        parsed.instant.timestamp()
+    );
+    if let Some(note) = parsed.note {
+        out.push_str(&format!("\n\nNote: {}", note));
+    }
+    Ok(out)
+}
+
+/// Month calendar with ISO week numbers; `month` is `YYYY-MM` or any date,
+/// and defaults to the current month in `tz`
+pub fn cal(month: Option<&str>, tz: Option<&str>) -> Result<String> {
+    let zone = tz.map(parse_zone).transpose()?.unwrap_or(Tz::UTC);
+    let day = match month {
+        None => Utc::now().with_timezone(&zone).date_naive(),
+        Some(text) => match NaiveDate::parse_from_str(&format!("{}-01", text.trim()), "%Y-%m-%d") {
+            Ok(first) => first,
+            Err(_) => parse_date(text, zone)?
+                .instant
+                .with_timezone(&zone)
+                .date_naive(),
+        },
+    };
+    Ok(calendar::month(day))
+}
+
+/// Output shared by `to_unix` and `add`
+fn describe_instant(
+    dt: DateTime<Utc>,
+    note: Option<String>,
+    zone: Option<Tz>,
+    show_tz: Option<&str>,
+    format: Option<&str>,
+) -> Result<String> {
+    if let Some(format) = format {
+        if let Some(note) = &note {
+            eprintln!("Note: {}", note);
         }
+        return format::render(dt.with_timezone(&zone.unwrap_or(Tz::UTC)), format);
     }
 
-    // Try ISO 8601
-    if let Ok(dt) = DateTime::parse_from_rfc3339(date) {
-        return Ok(format!(
-            "Unix timestamp: {}\n\
-             Milliseconds:   {}",
-            dt.timestamp(),
-            dt.timestamp_millis()
-        ));
+    let mut zones = parse_zones(show_tz)?;
+    if let Some(zone) = zone {
+        zones.insert(0, zone);
     }
+    let note = note
+        .map(|note| format!("\nNote: {}", note))
+        .unwrap_or_default();
+
+    Ok(format!(
+        "Unix timestamp: {}\n\
+         Milliseconds:   {}\n\
+         ISO:            {}{}{}",
+        dt.timestamp(),
+        dt.timestamp_millis(),
+        dt.to_rfc3339(),
+        zone_lines(dt, &zones),
+        note
+    ))
+}
 
-    anyhow::bail!(
-        "Could not parse date. Try formats like:\n\
-         • 2024-12-28 15:30:00\n\
-         • 2024-12-28\n\
-         • 2024-12-28T15:30:00Z (ISO 8601)"
+fn describe_duration(delta: TimeDelta) -> String {
+    format!(
+        "Duration:     {}\n\
+         ISO 8601:     {}\n\
+         Seconds:      {}\n\
+         Milliseconds: {}",
+        duration::human(delta),
+        duration::iso(delta),
+        delta.num_seconds(),
+        delta.num_milliseconds()
     )
 }
 
@@ -102,27 +445,165 @@ mod tests {
 
     #[test]
     fn test_now() {
-        let result = now().unwrap();
+        let result = now(None, None).unwrap();
         assert!(result.contains("Unix timestamp"));
         assert!(result.contains("UTC"));
     }
 
     #[test]
     fn test_from_unix() {
-        let result = from_unix(0).unwrap();
+        let result = from_unix(0, None, None, None).unwrap();
         assert!(result.contains("1970-01-01"));
     }
 
     #[test]
     fn test_from_unix_millis() {
         // Test that milliseconds are handled
-        let result = from_unix(1703808000000).unwrap();
+        let result = from_unix(1703808000000, None, None, None).unwrap();
         assert!(result.contains("2023"));
     }
 
+    #[test]
+    fn test_from_unix_units() {
+        // The same instant in every resolution
+        for timestamp in [
+            1_704_067_200,
+            1_704_067_200_123,
+            1_704_067_200_123_456,
+            1_704_067_200_123_456_789,
+        ] {
+            let result = from_unix(timestamp, None, None, None).unwrap();
+            assert!(result.contains("2024-01-01 00:00:00"), "{}", result);
+        }
+        assert!(from_unix(1_704_067_200_123_456, None, None, None)
+            .unwrap()
+            .contains("microseconds (auto-detected)"));
+
+        // Half a second before the epoch
+        let result = from_unix(-500, Some("ms"), None, None).unwrap();
+        assert!(result.contains("1969-12-31T23:59:59.500+00:00"));
+        assert!(from_unix(1, Some("fortnights"), None, None).is_err());
+    }
+
     #[test]
     fn test_to_unix() {
-        let result = to_unix("2024-01-01 00:00:00").unwrap();
+        let result = to_unix("2024-01-01 00:00:00", None, None, None).unwrap();
+        assert!(result.contains("1704067200"));
+    }
+
+    #[test]
+    fn test_to_unix_log_format() {
+        let result = to_unix("[01/Jan/2024:01:00:00 +0100]", None, None, None).unwrap();
         assert!(result.contains("1704067200"));
+        assert!(to_unix("whenever", None, None, None).is_err());
+    }
+
+    #[test]
+    fn test_time_zones() {
+        let result = to_unix(
+            "2024-01-01 09:00:00",
+            Some("Asia/Tokyo"),
+            Some("Europe/Berlin"),
+            None,
+        )
+        .unwrap();
+        assert!(result.contains("1704067200"));
+        assert!(result.contains("Europe/Berlin: 2024-01-01 01:00:00 CET (+01:00)"));
+
+        let zones = Some("America/New_York,Asia/Kolkata");
+        let result = from_unix(1704067200, None, zones, None).unwrap();
+        assert!(result.contains("2023-12-31 19:00:00 EST (-05:00)"));
+        assert!(result.contains("2024-01-01 05:30:00 IST (+05:30)"));
+
+        assert!(from_unix(0, None, Some("Mars/Olympus_Mons"), None).is_err());
+    }
+
+    #[test]
+    fn test_single_value_format() {
+        assert_eq!(
+            from_unix(1_735_387_200_000, None, None, Some("unix")).unwrap(),
+            "1735387200"
+        );
+        assert_eq!(
+            to_unix("2024-12-28 12:00", Some("Europe/Berlin"), None, Some("%H:%M %Z")).unwrap(),
+            "12:00 CET"
+        );
+        assert!(now(None, Some("unix-ms")).unwrap().parse::<i64>().is_ok());
+    }
+
+    #[test]
+    fn test_add_and_diff() {
+        let result = add("2024-12-28 12:00:00", "90d", None, None, Some("rfc3339")).unwrap();
+        assert_eq!(result, "2025-03-28T12:00:00Z");
+        let result = add("2024-12-28 12:00:00", "-1h30m", None, None, Some("unix")).unwrap();
+        assert_eq!(result, "1735381800");
+        // Months follow the calendar in --tz, not in UTC
+        let berlin = Some("Europe/Berlin");
+        let result = add("2024-01-31 00:30", "1mo", berlin, None, Some("%F %R")).unwrap();
+        assert_eq!(result, "2024-02-29 00:30");
+
+        let result = diff("2024-12-28 12:00:00", "2024-12-28T13:02:05Z", None).unwrap();
+        assert!(result.contains("1h 2m 5s"));
+        assert!(result.contains("PT1H2M5S"));
+        assert!(diff("2024-12-28", "later", None).is_err());
+    }
+
+    #[test]
+    fn test_duration() {
+        let result = duration("3725s").unwrap();
+        assert!(result.contains("Duration:     1h 2m 5s"));
+        assert!(result.contains("ISO 8601:     PT1H2M5S"));
+        assert!(duration("P1M").is_err());
+    }
+
+    #[test]
+    fn test_cron() {
+        let result = cron("*/15 9-17 * * MON-FRI", 3, Some("Europe/Berlin"), Some("%H:%M")).unwrap();
+        assert!(result.contains("every 15th minute"));
+        assert!(result.contains("Time zone:  Europe/Berlin"));
+        assert_eq!(result.lines().count(), 7);
+        assert!(cron("0 0 30 2 *", 1, None, None).unwrap().contains("No upcoming runs"));
+        assert!(cron("not a schedule", 1, None, None).is_err());
+    }
+
+    #[test]
+    fn test_from_id() {
+        let result = from_id("01ARZ3NDEKTSV4RRFFQ69G5FAV", None, None, None).unwrap();
+        assert!(result.starts_with("Type:  ULID\nUTC:   2016-07-30 23:54:10.259 UTC"));
+
+        let result = from_id("175928847299117063", None, None, None).unwrap();
+        assert!(result.contains("Parts: worker 1, process 0, sequence 7"));
+        assert!(result.contains("Note: Twitter and Discord"));
+
+        let result = from_id("175928847299117063", Some("twitter"), None, Some("unix")).unwrap();
+        assert_eq!(result, "1330779680");
+        assert!(from_id("x", Some("nope"), None, None).is_err());
+    }
+
+    #[test]
+    fn test_annotate_reads_path_as_is() {
+        // A relative name starting with `@` is still just a file name
+        let path = format!("@devkit-annotate-test-{}.log", std::process::id());
+        std::fs::write(&path, "started 1700000000\n").unwrap();
+        let result = annotate(&path, None, false, None);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), Output::Written);
+
+        let err = annotate("missing.log", None, false, None).unwrap_err();
+        assert!(err.to_string().starts_with("File not found"));
+    }
+
+    #[test]
+    fn test_info_and_cal() {
+        let result = info("2024-12-28", None).unwrap();
+        assert!(result.contains("ISO week:      2024-W52-6"));
+        assert!(result.contains("Unix time:     1735344000"));
+        // Late evening in New York is already the next day in UTC
+        let result = info("2024-12-31T23:30:00-05:00", Some("America/New_York")).unwrap();
+        assert!(result.contains("2024-12-31 Tuesday"));
+
+        assert!(cal(Some("2025-03"), None).unwrap().starts_with("       March 2025\nWk"));
+        assert!(cal(Some("2025-03-15"), None).unwrap().contains("March 2025"));
+        assert!(cal(Some("not a month"), None).is_err());
     }
 }
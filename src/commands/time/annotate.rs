@@ -0,0 +1,191 @@
+//! Timestamp annotation for log streams (`time annotate`).
+//!
+//! Lines are processed as raw bytes, so logs that are not valid UTF-8 pass
+//! through untouched apart from the timestamps found in them.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::bytes::{Captures, Regex};
+use std::io::{BufRead, Write};
+
+use super::{format, Unit};
+
+/// ISO 8601 date-times (with `T` or a space) and 10, 13, 16 or 19 digit
+/// epochs; seconds may carry a fractional part
+const PATTERN: &str = r"(?x)
+    (?P<iso>\b[0-9]{4}-[0-9]{2}-[0-9]{2}[T\ ][0-9]{2}:[0-9]{2}:[0-9]{2}
+        (?:[.,][0-9]{1,9})?(?:Z|[+-][0-9]{2}(?::?[0-9]{2})?)?)
+  | (?P<epoch>\b(?:[0-9]{10}(?:\.[0-9]{1,9})?|[0-9]{13}|[0-9]{16}|[0-9]{19})\b)";
+
+/// Epochs outside 2000-2099 are more likely IDs or counters than times
+const EPOCH_RANGE: std::ops::Range<i64> = 946_684_800..4_102_444_800;
+
+/// Copy `reader` to `out`, annotating each timestamp with its time in `zone`
+/// (or replacing it with `replace`), and return how many were found
+pub fn annotate<R: BufRead, W: Write>(
+    mut reader: R,
+    out: &mut W,
+    zone: Tz,
+    replace: bool,
+    format: &str,
+) -> Result<usize> {
+    // Fail early on a bad pattern rather than once per match
+    format::render(Utc::now().with_timezone(&zone), format)?;
+
+    let regex = Regex::new(PATTERN).context("Invalid timestamp pattern")?;
+    let mut count = 0;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader
+            .read_until(b'\n', &mut line)
+            .context("Failed to read input")?
+            == 0
+        {
+            break;
+        }
+        let rewritten = regex.replace_all(&line, |caps: &Captures| {
+            let original = &caps[0];
+            let Some(human) = parse_match(caps)
+                .and_then(|dt| format::render(dt.with_timezone(&zone), format).ok())
+            else {
+                return original.to_vec();
+            };
+            count += 1;
+            if replace {
+                human.into_bytes()
+            } else {
+                [original, b" [", human.as_bytes(), b"]"].concat()
+            }
+        });
+        out.write_all(&rewritten)
+            .context("Failed to write output")?;
+    }
+    Ok(count)
+}
+
+fn parse_match(caps: &Captures) -> Option<DateTime<Utc>> {
+    if let Some(iso) = caps.name("iso") {
+        return parse_iso(std::str::from_utf8(iso.as_bytes()).ok()?);
+    }
+    let epoch = std::str::from_utf8(caps.name("epoch")?.as_bytes()).ok()?;
+    let dt = match epoch.split_once('.') {
+        Some((secs, fraction)) => {
+            let nanos: u32 = format!("{:0<9}", fraction).parse().ok()?;
+            DateTime::from_timestamp(secs.parse().ok()?, nanos)?
+        }
+        None => {
+            let value: i64 = epoch.parse().ok()?;
+            Unit::detect(value).to_datetime(value)?
+        }
+    };
+    EPOCH_RANGE.contains(&dt.timestamp()).then_some(dt)
+}
+
+/// ISO timestamps without an offset are taken as UTC
+///
+/// `text` is ASCII (the pattern only matches ASCII digits), so byte
+/// offsets are safe.
+fn parse_iso(text: &str) -> Option<DateTime<Utc>> {
+    let mut normalized = text.replacen(' ', "T", 1).replace(',', ".");
+    let sign = normalized[19..].rfind(['+', '-']).map(|i| i + 19);
+
+    if let Some(sign) = sign {
+        // RFC 3339 needs hours and minutes with a colon: +05 and +0500 -> +05:00
+        match normalized.len() - sign {
+            3 => normalized.push_str(":00"),
+            5 => normalized.insert(sign + 3, ':'),
+            _ => {}
+        }
+    } else if !normalized.ends_with('Z') {
+        let naive = NaiveDateTime::parse_from_str(&normalized, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+        return Some(Utc.from_utc_datetime(&naive));
+    }
+    DateTime::parse_from_rfc3339(&normalized)
+        .ok()
+        .map(|dt| dt.to_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, zone: Tz, replace: bool) -> (String, usize) {
+        let mut out = Vec::new();
+        let count = annotate(
+            input.as_bytes(),
+            &mut out,
+            zone,
+            replace,
+            "%Y-%m-%d %H:%M:%S %Z",
+        )
+        .unwrap();
+        (String::from_utf8(out).unwrap(), count)
+    }
+
+    #[test]
+    fn test_epochs() {
+        let (out, count) = run(
+            "start=1735401600 ms=1735401600000 ns=1735401600000000000\nid=8234567890123456789 pid=4242\n",
+            Tz::UTC,
+            false,
+        );
+        assert_eq!(count, 3);
+        assert_eq!(
+            out,
+            "start=1735401600 [2024-12-28 16:00:00 UTC] \
+             ms=1735401600000 [2024-12-28 16:00:00 UTC] \
+             ns=1735401600000000000 [2024-12-28 16:00:00 UTC]\n\
+             id=8234567890123456789 pid=4242\n"
+        );
+    }
+
+    #[test]
+    fn test_iso_replace_in_zone() {
+        let (out, count) = run(
+            "[2024-12-28T16:00:00Z] ok\n2024-12-28 17:00:00,250+0100 warn\nt=1735401600.5\n",
+            Tz::Asia__Tokyo,
+            true,
+        );
+        assert_eq!(count, 3);
+        assert_eq!(
+            out,
+            "[2024-12-29 01:00:00 JST] ok\n2024-12-29 01:00:00 JST warn\nt=2024-12-29 01:00:00 JST\n"
+        );
+    }
+
+    #[test]
+    fn test_hour_only_offset() {
+        let (out, count) = run("a 2024-12-28T16:00:00.123+05 b\n", Tz::UTC, false);
+        assert_eq!(count, 1);
+        assert_eq!(
+            out,
+            "a 2024-12-28T16:00:00.123+05 [2024-12-28 11:00:00 UTC] b\n"
+        );
+    }
+
+    #[test]
+    fn test_ignores_non_ascii_digits() {
+        // Arabic-Indic digits match Unicode \d, but are not part of a timestamp
+        let (out, count) = run(
+            "2024-12-28T16:00:00+\u{660}\u{665} \u{661}\u{667}\u{663}\u{665}\u{664}\u{660}\u{661}\u{666}\u{660}\u{660}\n",
+            Tz::UTC,
+            true,
+        );
+        assert_eq!(count, 1);
+        assert_eq!(
+            out,
+            "2024-12-28 16:00:00 UTC+\u{660}\u{665} \u{661}\u{667}\u{663}\u{665}\u{664}\u{660}\u{661}\u{666}\u{660}\u{660}\n"
+        );
+    }
+
+    #[test]
+    fn test_passes_through_invalid_utf8() {
+        let mut out = Vec::new();
+        let input: &[u8] = b"\xff\xfe 1735401600\n";
+        annotate(input, &mut out, Tz::UTC, true, "unix-ms").unwrap();
+        assert_eq!(out, b"\xff\xfe 1735401600000\n");
+    }
+}
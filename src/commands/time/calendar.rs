@@ -0,0 +1,152 @@
+//! Calendar facts for `time info` and month grids for `time cal`.
+//!
+//! Weeks follow ISO 8601: they start on Monday and week 1 is the one
+//! containing the year's first Thursday. Business days are Monday to Friday;
+//! public holidays are not known.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+fn is_leap(year: i32) -> bool {
+    NaiveDate::from_ymd_opt(year, 2, 29).is_some()
+}
+
+fn days_in_year(year: i32) -> u32 {
+    if is_leap(year) {
+        366
+    } else {
+        365
+    }
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn month_end(date: NaiveDate) -> NaiveDate {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(date)
+}
+
+fn is_business_day(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// Monday-to-Friday days from `from` to `to`, both inclusive
+pub fn business_days(from: NaiveDate, to: NaiveDate) -> usize {
+    from.iter_days()
+        .take_while(|day| *day <= to)
+        .filter(|day| is_business_day(*day))
+        .count()
+}
+
+/// Facts about a date, one per line
+pub fn info(date: NaiveDate) -> String {
+    let week = date.iso_week();
+    let end = month_end(date);
+    let remaining = date.succ_opt().map_or(0, |next| business_days(next, end));
+
+    format!(
+        "Date:          {} {}\n\
+         ISO week:      {}-W{:02}-{}\n\
+         Day of year:   {} of {}\n\
+         Quarter:       Q{} {}\n\
+         Leap year:     {}\n\
+         Business days: {} in {}, {} after this date{}",
+        date.format("%Y-%m-%d"),
+        date.format("%A"),
+        week.year(),
+        week.week(),
+        date.weekday().number_from_monday(),
+        date.ordinal(),
+        days_in_year(date.year()),
+        (date.month() - 1) / 3 + 1,
+        date.year(),
+        if is_leap(date.year()) { "yes" } else { "no" },
+        business_days(month_start(date), end),
+        date.format("%B %Y"),
+        remaining,
+        if is_business_day(date) {
+            ""
+        } else {
+            " (weekend)"
+        }
+    )
+}
+
+/// A month grid starting on Monday, with ISO week numbers on the left
+pub fn month(date: NaiveDate) -> String {
+    let first = month_start(date);
+    let last = month_end(date);
+
+    let title = format!("{:^24}", first.format("%B %Y").to_string());
+    let mut out = format!("{}\nWk  Mo Tu We Th Fr Sa Su", title.trim_end());
+
+    let mut day = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+    while day <= last {
+        out.push_str(&format!("\n{:>2} ", day.iso_week().week()));
+        for _ in 0..7 {
+            if day.month() == first.month() {
+                out.push_str(&format!(" {:>2}", day.day()));
+            } else {
+                out.push_str("   ");
+            }
+            day = day.succ_opt().unwrap_or(day);
+        }
+        // Keep lines free of trailing blanks
+        out.truncate(out.trim_end().len());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_info() {
+        let facts = info(date(2024, 12, 28));
+        assert!(facts.contains("2024-12-28 Saturday"));
+        assert!(facts.contains("ISO week:      2024-W52-6"));
+        assert!(facts.contains("Day of year:   363 of 366"));
+        assert!(facts.contains("Q4 2024"));
+        assert!(facts.contains("Leap year:     yes"));
+        assert!(facts.contains("22 in December 2024, 2 after this date (weekend)"));
+
+        // Dec 30 2024 already belongs to ISO week 1 of 2025
+        assert!(info(date(2024, 12, 30)).contains("2025-W01-1"));
+    }
+
+    #[test]
+    fn test_business_days() {
+        assert_eq!(business_days(date(2024, 12, 23), date(2024, 12, 29)), 5);
+        assert_eq!(business_days(date(2024, 12, 28), date(2024, 12, 29)), 0);
+        assert_eq!(business_days(date(2024, 12, 29), date(2024, 12, 28)), 0);
+    }
+
+    #[test]
+    fn test_month() {
+        assert_eq!(
+            month(date(2024, 12, 15)),
+            [
+                "     December 2024",
+                "Wk  Mo Tu We Th Fr Sa Su",
+                "48                     1",
+                "49   2  3  4  5  6  7  8",
+                "50   9 10 11 12 13 14 15",
+                "51  16 17 18 19 20 21 22",
+                "52  23 24 25 26 27 28 29",
+                " 1  30 31",
+            ]
+            .join("\n")
+        );
+    }
+}
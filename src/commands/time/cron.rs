@@ -0,0 +1,701 @@
+//! Cron expressions: validation, English explanations and upcoming runs.
+//!
+//! Supports the standard five fields (`min hour dom month dow`), Quartz-style
+//! six and seven fields (leading seconds, trailing year), `@daily`-style
+//! macros, and the Quartz `L`, `W`, `#` and `?` extensions. When both
+//! day-of-month and day-of-week are restricted a day matches either one, as
+//! in Vixie cron.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, TimeZone, Timelike};
+use chrono_tz::Tz;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const DAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+const DAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// How far ahead to look for the next run before giving up
+const SEARCH_YEARS: i64 = 400;
+
+/// One comma-separated element of a field
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    /// `*` or `?`
+    All,
+    /// `a`, `a-b`, `a/n`, `a-b/n` or `*/n`; ranges may wrap (`FRI-MON`)
+    Range {
+        start: u32,
+        end: u32,
+        step: u32,
+        star: bool,
+    },
+    /// `L` or `L-n` in day-of-month
+    LastDay(u32),
+    /// `LW` in day-of-month
+    LastWeekday,
+    /// `nW` in day-of-month
+    NearestWeekday(u32),
+    /// `nL` in day-of-week
+    LastOf(u32),
+    /// `n#k` in day-of-week
+    Nth(u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Second,
+    Minute,
+    Hour,
+    DayOfMonth,
+    Month,
+    DayOfWeek,
+    Year,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Second => "second",
+            Kind::Minute => "minute",
+            Kind::Hour => "hour",
+            Kind::DayOfMonth => "day-of-month",
+            Kind::Month => "month",
+            Kind::DayOfWeek => "day-of-week",
+            Kind::Year => "year",
+        }
+    }
+
+    /// Accepted numeric range; Quartz numbers weekdays 1-7 from Sunday,
+    /// standard cron 0-7 with both 0 and 7 meaning Sunday
+    fn bounds(self, quartz: bool) -> (u32, u32) {
+        match self {
+            Kind::Second | Kind::Minute => (0, 59),
+            Kind::Hour => (0, 23),
+            Kind::DayOfMonth => (1, 31),
+            Kind::Month => (1, 12),
+            Kind::DayOfWeek if quartz => (1, 7),
+            Kind::DayOfWeek => (0, 7),
+            Kind::Year => (1970, 2099),
+        }
+    }
+
+    fn label(self, value: u32) -> String {
+        match self {
+            Kind::Month => MONTH_NAMES[(value - 1) as usize].to_string(),
+            Kind::DayOfWeek => DAY_NAMES[(value % 7) as usize].to_string(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    kind: Kind,
+    text: String,
+    parts: Vec<Part>,
+    min: u32,
+    max: u32,
+}
+
+impl Field {
+    fn parse(text: &str, kind: Kind, quartz: bool) -> Result<Field> {
+        let (min, max) = kind.bounds(quartz);
+        let mut field = Field {
+            kind,
+            text: text.to_string(),
+            parts: Vec::new(),
+            min,
+            max,
+        };
+        for token in text.split(',') {
+            let part = field
+                .parse_part(&token.to_uppercase(), quartz)
+                .with_context(|| format!("Invalid {} field '{}'", kind.name(), text))?;
+            field.parts.push(part);
+        }
+
+        // Store weekdays as 0-6 from Sunday whatever the input numbering
+        if kind == Kind::DayOfWeek && quartz {
+            for part in &mut field.parts {
+                match part {
+                    Part::Range { start, end, .. } => {
+                        *start -= 1;
+                        *end -= 1;
+                    }
+                    Part::LastOf(day) | Part::Nth(day, _) => *day -= 1,
+                    _ => {}
+                }
+            }
+            field.min = 0;
+            field.max = 6;
+        }
+        Ok(field)
+    }
+
+    fn parse_part(&self, token: &str, quartz: bool) -> Result<Part> {
+        let kind = self.kind;
+        let days = matches!(kind, Kind::DayOfMonth | Kind::DayOfWeek);
+
+        if token == "*" || (token == "?" && days) {
+            return Ok(Part::All);
+        }
+        if kind == Kind::DayOfMonth {
+            if token == "L" {
+                return Ok(Part::LastDay(0));
+            }
+            if token == "LW" {
+                return Ok(Part::LastWeekday);
+            }
+            if let Some(offset) = token.strip_prefix("L-") {
+                return Ok(Part::LastDay(self.number(offset, 0, 30)?));
+            }
+            if let Some(day) = token.strip_suffix('W') {
+                return Ok(Part::NearestWeekday(self.value(day)?));
+            }
+        }
+        if kind == Kind::DayOfWeek {
+            if token == "L" {
+                // Quartz: the last day of the week, Saturday
+                return Ok(Part::Range {
+                    start: if quartz { 7 } else { 6 },
+                    end: if quartz { 7 } else { 6 },
+                    step: 1,
+                    star: false,
+                });
+            }
+            if let Some(day) = token.strip_suffix('L') {
+                return Ok(Part::LastOf(self.value(day)?));
+            }
+            if let Some((day, nth)) = token.split_once('#') {
+                return Ok(Part::Nth(self.value(day)?, self.number(nth, 1, 5)?));
+            }
+        }
+
+        let (base, step) = match token.split_once('/') {
+            Some((base, step)) => (base, Some(self.number(step, 1, self.max)?)),
+            None => (token, None),
+        };
+        let (start, end, star) = if base == "*" {
+            (self.min, self.max, true)
+        } else if let Some((start, end)) = base.split_once('-') {
+            (self.value(start)?, self.value(end)?, false)
+        } else {
+            let start = self.value(base)?;
+            // `a/n` runs from a to the end of the range
+            (start, if step.is_some() { self.max } else { start }, false)
+        };
+        Ok(Part::Range {
+            start,
+            end,
+            step: step.unwrap_or(1),
+            star,
+        })
+    }
+
+    /// A value in the field's range, by number or (for months and weekdays) name
+    fn value(&self, text: &str) -> Result<u32> {
+        let names: &[&str] = match self.kind {
+            Kind::Month => &MONTHS,
+            Kind::DayOfWeek => &DAYS,
+            _ => &[],
+        };
+        if let Some(index) = names.iter().position(|name| *name == text) {
+            // Names are 1-based for months and Quartz weekdays
+            return Ok(index as u32 + self.min.min(1));
+        }
+        self.number(text, self.min, self.max)
+    }
+
+    fn number(&self, text: &str, min: u32, max: u32) -> Result<u32> {
+        let value: u32 = text
+            .parse()
+            .map_err(|_| anyhow::anyhow!("'{}' is not a number", text))?;
+        if value < min || value > max {
+            bail!("{} is out of range {}-{}", value, min, max);
+        }
+        Ok(value)
+    }
+
+    /// Written as `*` or `?`, matching everything
+    fn unrestricted(&self) -> bool {
+        self.parts.iter().all(|part| *part == Part::All)
+    }
+
+    /// Vixie cron treats a day field starting with `*` as unrestricted when
+    /// combining day-of-month and day-of-week, even with a step
+    fn starred(&self) -> bool {
+        self.text.starts_with(['*', '?'])
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        let span = self.max - self.min + 1;
+        self.parts.iter().any(|part| match *part {
+            Part::All => true,
+            Part::Range {
+                start, end, step, ..
+            } => {
+                let offset = (value + span - start) % span;
+                let length = (end + span - start) % span;
+                (self.min..=self.max).contains(&value)
+                    && offset <= length
+                    && offset / step * step == offset
+            }
+            _ => false,
+        })
+    }
+
+    fn values(&self) -> Vec<u32> {
+        (self.min..=self.max)
+            .filter(|&v| self.contains(v))
+            .collect()
+    }
+
+    /// The single value of a field like `30`, if that is all it holds
+    fn single(&self) -> Option<u32> {
+        match self.parts.as_slice() {
+            [Part::Range { start, end, .. }] if start == end => Some(*start),
+            _ => None,
+        }
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = date.day();
+        let last = last_day(date);
+        let weekday = date.weekday().num_days_from_sunday();
+
+        match self.kind {
+            Kind::DayOfMonth => self.parts.iter().any(|part| match *part {
+                Part::LastDay(offset) => last > offset && day == last - offset,
+                Part::LastWeekday => day == nearest_weekday(date, last),
+                Part::NearestWeekday(target) => {
+                    target <= last && day == nearest_weekday(date, target)
+                }
+                _ => self.contains(day),
+            }),
+            _ => self.parts.iter().any(|part| match *part {
+                Part::LastOf(target) => weekday == target % 7 && day + 7 > last,
+                Part::Nth(target, nth) => weekday == target % 7 && (day - 1) / 7 + 1 == nth,
+                // Standard cron also accepts 7 for Sunday
+                _ => self.contains(weekday) || (weekday == 0 && self.contains(7)),
+            }),
+        }
+    }
+
+    /// English for the field, e.g. "every 15th minute" or "Monday and Friday"
+    fn describe(&self) -> String {
+        let unit = self.kind.name();
+        // Names stand on their own; numbers need their unit
+        let named = matches!(self.kind, Kind::Month | Kind::DayOfWeek);
+        let label = |value: u32| self.kind.label(value);
+
+        let values: Vec<u32> = self
+            .parts
+            .iter()
+            .filter_map(|part| match *part {
+                Part::Range { start, end, .. } if start == end => Some(start),
+                _ => None,
+            })
+            .collect();
+        if values.len() == self.parts.len() {
+            let list = join(values.into_iter().map(label).collect());
+            return if named {
+                list
+            } else {
+                format!("{} {}", unit, list)
+            };
+        }
+
+        let phrases = self.parts.iter().map(|part| match *part {
+            Part::All => format!("every {}", unit),
+            Part::Range { start, end, .. } if start == end && named => label(start),
+            Part::Range { start, end, .. } if start == end => format!("{} {}", unit, start),
+            Part::Range {
+                step: 1,
+                star: true,
+                ..
+            } => format!("every {}", unit),
+            Part::Range {
+                step, star: true, ..
+            } => format!("every {} {}", ordinal(step), unit),
+            Part::Range {
+                start, end, step, ..
+            } => {
+                let every = if step == 1 {
+                    format!("every {}", unit)
+                } else {
+                    format!("every {} {}", ordinal(step), unit)
+                };
+                format!("{} from {} through {}", every, label(start), label(end))
+            }
+            Part::LastDay(0) => "the last day of the month".to_string(),
+            Part::LastDay(offset) => format!("{} days before the last day of the month", offset),
+            Part::LastWeekday => "the last weekday of the month".to_string(),
+            Part::NearestWeekday(day) => format!("the weekday nearest day {} of the month", day),
+            Part::LastOf(day) => format!("the last {} of the month", label(day)),
+            Part::Nth(day, nth) => format!("the {} {} of the month", ordinal(nth), label(day)),
+        });
+        join(phrases.collect())
+    }
+}
+
+fn last_day(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+/// The weekday closest to `target` in the month of `date`, never crossing
+/// into another month (Quartz `W`)
+fn nearest_weekday(date: NaiveDate, target: u32) -> u32 {
+    let last = last_day(date);
+    let Some(day) = date.with_day(target) else {
+        return 0;
+    };
+    match day.weekday().num_days_from_sunday() {
+        6 if target == 1 => 3,
+        6 => target - 1,
+        0 if target == last => target - 2,
+        0 => target + 1,
+        _ => target,
+    }
+}
+
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// "a", "a and b", "a, b, and c"
+fn join(items: Vec<String>) -> String {
+    match items.as_slice() {
+        [] => String::new(),
+        [one] => one.clone(),
+        [a, b] => format!("{} and {}", a, b),
+        [rest @ .., last] => format!("{}, and {}", rest.join(", "), last),
+    }
+}
+
+/// A parsed cron expression
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    seconds: Option<Field>,
+    minutes: Field,
+    hours: Field,
+    days: Field,
+    months: Field,
+    weekdays: Field,
+    years: Option<Field>,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Schedule> {
+        let expanded = match expression.trim().to_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            "@reboot" => bail!("@reboot runs at startup and has no schedule"),
+            other if other.starts_with('@') => bail!("Unknown cron macro: {}", expression.trim()),
+            _ => expression,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let quartz = fields.len() > 5;
+        let (seconds, rest, year) = match fields.as_slice() {
+            rest if rest.len() == 5 => (None, rest, None),
+            [seconds, rest @ ..] if rest.len() == 5 => (Some(*seconds), rest, None),
+            [seconds, rest @ .., year] if rest.len() == 5 => (Some(*seconds), rest, Some(*year)),
+            _ => bail!(
+                "Expected 5 fields (minute hour day month weekday), or 6-7 with seconds and year; got {}",
+                fields.len()
+            ),
+        };
+
+        let field = |text: &str, kind| Field::parse(text, kind, quartz);
+        Ok(Schedule {
+            seconds: seconds.map(|text| field(text, Kind::Second)).transpose()?,
+            minutes: field(rest[0], Kind::Minute)?,
+            hours: field(rest[1], Kind::Hour)?,
+            days: field(rest[2], Kind::DayOfMonth)?,
+            months: field(rest[3], Kind::Month)?,
+            weekdays: field(rest[4], Kind::DayOfWeek)?,
+            years: year.map(|text| field(text, Kind::Year)).transpose()?,
+        })
+    }
+
+    /// The schedule in English, in the style of crontab.guru
+    pub fn explain(&self) -> String {
+        let second = self.seconds.as_ref().map_or(Some(0), Field::single);
+        let mut out = match (second, self.minutes.single(), self.hours.single()) {
+            (Some(0), Some(minute), Some(hour)) => format!("At {:02}:{:02}", hour, minute),
+            (Some(second), Some(minute), Some(hour)) => {
+                format!("At {:02}:{:02}:{:02}", hour, minute, second)
+            }
+            _ => {
+                let mut pieces = Vec::new();
+                if let Some(seconds) = &self.seconds {
+                    if seconds.single() != Some(0) {
+                        pieces.push(seconds.describe());
+                    }
+                }
+                pieces.push(self.minutes.describe());
+                if !self.hours.unrestricted() {
+                    pieces.push(self.hours.describe());
+                }
+                format!("At {}", pieces.join(" past "))
+            }
+        };
+
+        match (self.days.unrestricted(), self.weekdays.unrestricted()) {
+            (true, true) => {}
+            (false, true) => out += &format!(" on {}", self.days.describe()),
+            (true, false) => out += &format!(" on {}", self.weekdays.describe()),
+            (false, false) => {
+                let either = !self.days.starred() && !self.weekdays.starred();
+                out += &format!(
+                    " on {} {} on {}",
+                    self.days.describe(),
+                    if either { "or" } else { "and" },
+                    self.weekdays.describe()
+                )
+            }
+        }
+        if !self.months.unrestricted() {
+            out += &format!(" in {}", self.months.describe());
+        }
+        if let Some(years) = self.years.as_ref().filter(|years| !years.unrestricted()) {
+            out += &format!(" in {}", years.describe());
+        }
+        out + "."
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(date.month()) {
+            return false;
+        }
+        if let Some(years) = &self.years {
+            if !u32::try_from(date.year()).is_ok_and(|year| years.contains(year)) {
+                return false;
+            }
+        }
+
+        let day = self.days.matches_day(date);
+        let weekday = self.weekdays.matches_day(date);
+        if self.days.starred() || self.weekdays.starred() {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// The first run strictly after `after`, in wall-clock time of its zone
+    ///
+    /// Times skipped by a DST gap do not run; times repeated by a fold run
+    /// once, at the earlier instant.
+    pub fn next_after(&self, after: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let seconds = self.seconds.as_ref().map_or(vec![0], Field::values);
+        let minutes = self.minutes.values();
+        let hours = self.hours.values();
+
+        let start = after.naive_local().with_nanosecond(0)?;
+        let mut date = start.date();
+        let limit = date + Duration::days(SEARCH_YEARS * 366);
+
+        while date <= limit {
+            if self.matches_date(date) {
+                for &hour in &hours {
+                    for &minute in &minutes {
+                        for &second in &seconds {
+                            let naive = date.and_hms_opt(hour, minute, second)?;
+                            if naive < start {
+                                continue;
+                            }
+                            let candidate = match tz.from_local_datetime(&naive) {
+                                LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt,
+                                LocalResult::None => continue,
+                            };
+                            if candidate > after {
+                                return Some(candidate);
+                            }
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    /// The next `count` runs after `after`
+    pub fn upcoming(&self, after: DateTime<Tz>, count: usize) -> Vec<DateTime<Tz>> {
+        let mut runs = Vec::with_capacity(count);
+        let mut current = after;
+        while runs.len() < count {
+            let Some(next) = self.next_after(current) else {
+                break;
+            };
+            runs.push(next);
+            current = next;
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explain(expression: &str) -> String {
+        Schedule::parse(expression).unwrap().explain()
+    }
+
+    fn runs(expression: &str, after: &str, tz: Tz, count: usize) -> Vec<String> {
+        let after = DateTime::parse_from_rfc3339(after)
+            .unwrap()
+            .with_timezone(&tz);
+        Schedule::parse(expression)
+            .unwrap()
+            .upcoming(after, count)
+            .iter()
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S %a").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            explain("*/15 9-17 * * MON-FRI"),
+            "At every 15th minute past every hour from 9 through 17 \
+             on every day-of-week from Monday through Friday."
+        );
+        assert_eq!(explain("0 9 * * *"), "At 09:00.");
+        assert_eq!(explain("@weekly"), "At 00:00 on Sunday.");
+        assert_eq!(
+            explain("0 0,12 1 */2 *"),
+            "At minute 0 past hour 0 and 12 on day-of-month 1 in every 2nd month."
+        );
+        assert_eq!(
+            explain("30 0 8 ? * 6#3 2025"),
+            "At 08:00:30 on the 3rd Friday of the month in year 2025."
+        );
+        assert_eq!(
+            explain("0 30 10 L * ?"),
+            "At 10:30 on the last day of the month."
+        );
+        assert_eq!(
+            explain("5 4 1,15 * 1"),
+            "At 04:05 on day-of-month 1 and 15 or on Monday."
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        for expression in [
+            "* * * *",
+            "61 * * * *",
+            "* 24 * * *",
+            "* * * FOO *",
+            "*/0 * * * *",
+            "* * ? * *x",
+            "@reboot",
+            "0 0 0 1 1 ? 1969",
+        ] {
+            assert!(Schedule::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_next_runs() {
+        let utc = Tz::UTC;
+        // Friday evening: the next runs are on Monday morning
+        assert_eq!(
+            runs("*/15 9-17 * * MON-FRI", "2024-12-27T17:50:00Z", utc, 3),
+            [
+                "2024-12-30 09:00:00 Mon",
+                "2024-12-30 09:15:00 Mon",
+                "2024-12-30 09:30:00 Mon"
+            ]
+        );
+        // Vixie OR semantics: the 1st or any Sunday
+        assert_eq!(
+            runs("0 0 1 * 0", "2024-12-28T00:00:00Z", utc, 3),
+            [
+                "2024-12-29 00:00:00 Sun",
+                "2025-01-01 00:00:00 Wed",
+                "2025-01-05 00:00:00 Sun"
+            ]
+        );
+        // The next leap day is years away
+        assert_eq!(
+            runs("0 0 0 29 2 ?", "2024-03-01T00:00:00Z", utc, 1),
+            ["2028-02-29 00:00:00 Tue"]
+        );
+        assert_eq!(
+            runs("0 0 12 LW * ?", "2024-08-01T00:00:00Z", utc, 2),
+            ["2024-08-30 12:00:00 Fri", "2024-09-30 12:00:00 Mon"]
+        );
+        assert_eq!(
+            runs("0 0 12 15W * ?", "2024-06-01T00:00:00Z", utc, 1),
+            ["2024-06-14 12:00:00 Fri"]
+        );
+        assert_eq!(
+            runs("0 0 9 ? * 6L", "2024-12-01T00:00:00Z", utc, 1),
+            ["2024-12-27 09:00:00 Fri"]
+        );
+        assert_eq!(
+            runs("*/20 * * * * *", "2024-12-28T12:00:00Z", utc, 2),
+            ["2024-12-28 12:00:20 Sat", "2024-12-28 12:00:40 Sat"]
+        );
+    }
+
+    #[test]
+    fn test_dst() {
+        let ny = Tz::America__New_York;
+        // 02:30 does not exist on 2024-03-10 in New York
+        assert_eq!(
+            runs("30 2 * * *", "2024-03-09T12:00:00Z", ny, 2),
+            ["2024-03-11 02:30:00 Mon", "2024-03-12 02:30:00 Tue"]
+        );
+        // 01:30 happens twice on 2024-11-03 but runs once
+        assert_eq!(
+            runs("30 1 * * *", "2024-11-02T12:00:00Z", ny, 2),
+            ["2024-11-03 01:30:00 Sun", "2024-11-04 01:30:00 Mon"]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! Durations for `time add`, `time diff` and `time duration`.
+//!
+//! Accepts compact forms (`90d`, `1h30m`, `-2w`), spelled-out ones
+//! (`2 hours 5 minutes`), bare seconds (`3725`) and ISO 8601 (`PT1H2M5S`).
+//! Months and years are kept apart from the exact part because their length
+//! depends on the date they are added to.
+
+use chrono::{DateTime, Months, TimeDelta, Utc};
+use chrono_tz::Tz;
+
+use super::parse::{resolve, Parsed};
+
+/// A length of time: whole calendar months plus an exact duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub months: i64,
+    pub exact: TimeDelta,
+}
+
+impl Span {
+    const ZERO: Span = Span {
+        months: 0,
+        exact: TimeDelta::zero(),
+    };
+
+    /// `amount` of a unit such as `h`, `hours`, `mo` or `ms`
+    pub fn of(amount: i64, unit: &str) -> Option<Span> {
+        let unit = match unit {
+            "ms" | "s" | "mo" => unit,
+            _ => match unit.strip_suffix('s') {
+                Some(singular) if !singular.is_empty() => singular,
+                _ => unit,
+            },
+        };
+        let millis = match unit {
+            "ms" | "msec" | "millisecond" => 1,
+            "s" | "sec" | "second" => 1_000,
+            "m" | "min" | "minute" => 60_000,
+            "h" | "hr" | "hour" => 3_600_000,
+            "d" | "day" => 86_400_000,
+            "w" | "wk" | "week" => 604_800_000,
+            "mo" | "month" => return Some(Span::months(amount)),
+            "y" | "yr" | "year" => return Some(Span::months(amount.checked_mul(12)?)),
+            _ => return None,
+        };
+        Some(Span {
+            months: 0,
+            exact: TimeDelta::try_milliseconds(amount.checked_mul(millis)?)?,
+        })
+    }
+
+    fn months(months: i64) -> Span {
+        Span {
+            months,
+            exact: TimeDelta::zero(),
+        }
+    }
+
+    fn checked_add(self, other: Span) -> Option<Span> {
+        Some(Span {
+            months: self.months.checked_add(other.months)?,
+            exact: self.exact.checked_add(&other.exact)?,
+        })
+    }
+
+    pub fn negate(self) -> Span {
+        Span {
+            months: -self.months,
+            exact: -self.exact,
+        }
+    }
+
+    /// Move `from` by the span, months first so `+1mo` keeps the day of month
+    ///
+    /// Months are added to the wall-clock time in `tz`; a result in a DST
+    /// fold or gap is resolved with a note.
+    pub fn apply(self, from: DateTime<Utc>, tz: Tz) -> Option<Parsed> {
+        let mut moved = Parsed {
+            instant: from,
+            note: None,
+        };
+        if self.months != 0 {
+            let local = from.with_timezone(&tz).naive_local();
+            let months = Months::new(u32::try_from(self.months.unsigned_abs()).ok()?);
+            let shifted = if self.months < 0 {
+                local.checked_sub_months(months)?
+            } else {
+                local.checked_add_months(months)?
+            };
+            moved = resolve(shifted, tz);
+        }
+        moved.instant = moved.instant.checked_add_signed(self.exact)?;
+        Some(moved)
+    }
+}
+
+/// Parse a duration, with an optional leading sign
+pub fn parse(text: &str) -> Option<Span> {
+    let text = text.trim();
+    let (negative, body) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let body = body.trim_start();
+
+    let span = if body.starts_with(['P', 'p']) {
+        parse_iso(&body[1..])?
+    } else if !body.is_empty() && body.bytes().all(|b| b.is_ascii_digit()) {
+        Span::of(body.parse().ok()?, "s")?
+    } else {
+        parse_units(&body.to_lowercase())?
+    };
+
+    Some(if negative { span.negate() } else { span })
+}
+
+/// `1h30m`, `2 hours 5 minutes`, `1d, 2h and 3m`
+fn parse_units(text: &str) -> Option<Span> {
+    let mut span = Span::ZERO;
+    let mut rest = text;
+    let mut parts = 0;
+
+    loop {
+        rest = rest.trim_start_matches([' ', ',']);
+        rest = rest.strip_prefix("and ").unwrap_or(rest);
+        if rest.is_empty() {
+            break;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        span = span.checked_add(Span::of(amount, &rest[..letters])?)?;
+        rest = &rest[letters..];
+        parts += 1;
+    }
+
+    (parts > 0).then_some(span)
+}
+
+/// ISO 8601 `PnYnMnWnDTnHnMnS` with everything after the `P`; only seconds
+/// may be fractional
+fn parse_iso(text: &str) -> Option<Span> {
+    let (date, time) = match text.split_once(['T', 't']) {
+        Some((date, time)) if !time.is_empty() => (date, Some(time)),
+        Some(_) => return None,
+        None => (text, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return None;
+    }
+
+    let mut span = Span::ZERO;
+    for (part, in_time) in [(date, false), (time.unwrap_or(""), true)] {
+        let mut rest = part;
+        while !rest.is_empty() {
+            let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')?;
+            let number = rest[..end].replace(',', ".");
+            let designator = rest[end..].chars().next()?.to_ascii_uppercase();
+            rest = &rest[end + 1..];
+
+            let piece = match (designator, in_time) {
+                ('S', true) => {
+                    let seconds: f64 = number.parse().ok()?;
+                    Span {
+                        months: 0,
+                        exact: TimeDelta::try_milliseconds((seconds * 1000.0).round() as i64)?,
+                    }
+                }
+                _ if number.contains('.') => return None,
+                ('Y', false) => Span::of(number.parse().ok()?, "y")?,
+                ('M', false) => Span::of(number.parse().ok()?, "mo")?,
+                ('W', false) => Span::of(number.parse().ok()?, "w")?,
+                ('D', false) => Span::of(number.parse().ok()?, "d")?,
+                ('H', true) => Span::of(number.parse().ok()?, "h")?,
+                ('M', true) => Span::of(number.parse().ok()?, "m")?,
+                _ => return None,
+            };
+            span = span.checked_add(piece)?;
+        }
+    }
+    Some(span)
+}
+
+/// Split an exact duration into days, hours, minutes, seconds and millis
+fn components(delta: TimeDelta) -> [i64; 5] {
+    let millis = delta.num_milliseconds().unsigned_abs() as i64;
+    [
+        millis / 86_400_000,
+        millis / 3_600_000 % 24,
+        millis / 60_000 % 60,
+        millis / 1_000 % 60,
+        millis % 1_000,
+    ]
+}
+
+/// Human-readable form such as `1h 2m 5s` or `-3d 4h`
+pub fn human(delta: TimeDelta) -> String {
+    let parts: Vec<String> = components(delta)
+        .into_iter()
+        .zip(["d", "h", "m", "s", "ms"])
+        .filter(|(value, _)| *value != 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    match (parts.is_empty(), delta < TimeDelta::zero()) {
+        (true, _) => "0s".to_string(),
+        (false, true) => format!("-{}", parts.join(" ")),
+        (false, false) => parts.join(" "),
+    }
+}
+
+/// ISO 8601 form such as `PT1H2M5S` or `P3DT4H`
+pub fn iso(delta: TimeDelta) -> String {
+    let [days, hours, minutes, seconds, millis] = components(delta);
+    let mut out = String::from(if delta < TimeDelta::zero() { "-P" } else { "P" });
+
+    if days != 0 {
+        out.push_str(&format!("{}D", days));
+    }
+    if hours != 0 || minutes != 0 || seconds != 0 || millis != 0 || days == 0 {
+        out.push('T');
+        if hours != 0 {
+            out.push_str(&format!("{}H", hours));
+        }
+        if minutes != 0 {
+            out.push_str(&format!("{}M", minutes));
+        }
+        if millis != 0 {
+            let fraction = format!("{:03}", millis);
+            out.push_str(&format!("{}.{}S", seconds, fraction.trim_end_matches('0')));
+        } else if seconds != 0 || out.ends_with('T') {
+            out.push_str(&format!("{}S", seconds));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn seconds(text: &str) -> i64 {
+        let span = parse(text).unwrap_or_else(|| panic!("failed to parse {:?}", text));
+        assert_eq!(span.months, 0, "{}", text);
+        span.exact.num_seconds()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(seconds("3725s"), 3725);
+        assert_eq!(seconds("3725"), 3725);
+        assert_eq!(seconds("1h2m5s"), 3725);
+        assert_eq!(seconds("1h 2m 5s"), 3725);
+        assert_eq!(seconds("1 hour, 2 minutes and 5 seconds"), 3725);
+        assert_eq!(seconds("PT1H2M5S"), 3725);
+        assert_eq!(seconds("P1DT1S"), 86_401);
+        assert_eq!(seconds("-2w"), -1_209_600);
+        assert_eq!(parse("1500ms").unwrap().exact.num_milliseconds(), 1500);
+        assert_eq!(parse("PT0.25S").unwrap().exact.num_milliseconds(), 250);
+        assert_eq!(parse("P1Y2M").unwrap().months, 14);
+        assert_eq!(parse("3 months").unwrap().months, 3);
+
+        for bad in ["", "h", "3x", "P", "PT", "P1H", "P1.5D", "1h30"] {
+            assert!(parse(bad).is_none(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let cases = [
+            (3_725_000, "1h 2m 5s", "PT1H2M5S"),
+            (0, "0s", "PT0S"),
+            (90 * 86_400_000, "90d", "P90D"),
+            (86_400_000 + 7_200_000, "1d 2h", "P1DT2H"),
+            (1_500, "1s 500ms", "PT1.5S"),
+            (-60_000, "-1m", "-PT1M"),
+        ];
+        for (millis, expected_human, expected_iso) in cases {
+            let delta = TimeDelta::milliseconds(millis);
+            assert_eq!(human(delta), expected_human);
+            assert_eq!(iso(delta), expected_iso);
+        }
+    }
+
+    #[test]
+    fn test_apply_months_then_exact() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap();
+        let later = parse("1mo 1d").unwrap().apply(from, Tz::UTC).unwrap();
+        // Jan 31 + 1 month clamps to Feb 29 in a leap year
+        assert_eq!(later.instant.to_rfc3339(), "2024-03-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn test_apply_months_on_local_calendar() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        // 2024-01-31 00:30 in Berlin is still January 30 in UTC
+        let from = Utc.with_ymd_and_hms(2024, 1, 30, 23, 30, 0).unwrap();
+        let later = parse("1mo").unwrap().apply(from, berlin).unwrap();
+        assert_eq!(
+            later.instant.with_timezone(&berlin).to_rfc3339(),
+            "2024-02-29T00:30:00+01:00"
+        );
+        assert_eq!(later.note, None);
+
+        // 02:30 on March 31 is skipped by the spring change
+        let from = Utc.with_ymd_and_hms(2024, 1, 31, 1, 30, 0).unwrap();
+        let later = parse("2mo").unwrap().apply(from, berlin).unwrap();
+        assert_eq!(
+            later.instant.with_timezone(&berlin).to_rfc3339(),
+            "2024-03-31T03:30:00+02:00"
+        );
+        assert!(later.note.unwrap().contains("does not exist"));
+    }
+}
@@ -0,0 +1,67 @@
+//! Single-value output for `--format`: named presets or `strftime` patterns.
+
+use anyhow::{bail, Result};
+use chrono::format::StrftimeItems;
+use chrono::{DateTime, SecondsFormat};
+use chrono_tz::Tz;
+
+/// Names accepted by `--format` besides `strftime` patterns
+pub const PRESETS: [&str; 6] = ["rfc3339", "rfc2822", "iso-week", "http", "unix", "unix-ms"];
+
+/// Render `dt` with a preset name or a `strftime` pattern
+pub fn render(dt: DateTime<Tz>, format: &str) -> Result<String> {
+    Ok(match format {
+        "rfc3339" => dt.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        "rfc2822" => dt.to_rfc2822(),
+        "iso-week" => dt.format("%G-W%V-%u").to_string(),
+        // HTTP-date is always expressed in GMT
+        "http" => dt.to_utc().format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        "unix" => dt.timestamp().to_string(),
+        "unix-ms" => dt.timestamp_millis().to_string(),
+        pattern if pattern.contains('%') => {
+            let Ok(items) = StrftimeItems::new(pattern).parse() else {
+                bail!("Invalid strftime pattern: {}", pattern);
+            };
+            dt.format_with_items(items.into_iter()).to_string()
+        }
+        other => bail!(
+            "Unknown format: {}. Use a strftime pattern like '%Y%m%dT%H%M%S' or one of: {}",
+            other,
+            PRESETS.join(", ")
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_presets() {
+        let dt = Tz::UTC.with_ymd_and_hms(2024, 12, 28, 12, 0, 5).unwrap();
+        assert_eq!(render(dt, "rfc3339").unwrap(), "2024-12-28T12:00:05Z");
+        assert_eq!(
+            render(dt, "rfc2822").unwrap(),
+            "Sat, 28 Dec 2024 12:00:05 +0000"
+        );
+        assert_eq!(render(dt, "iso-week").unwrap(), "2024-W52-6");
+        assert_eq!(render(dt, "http").unwrap(), "Sat, 28 Dec 2024 12:00:05 GMT");
+        assert_eq!(render(dt, "unix").unwrap(), "1735387205");
+        assert_eq!(render(dt, "unix-ms").unwrap(), "1735387205000");
+    }
+
+    #[test]
+    fn test_strftime() {
+        let dt = Tz::Europe__Berlin
+            .with_ymd_and_hms(2024, 12, 28, 13, 0, 0)
+            .unwrap();
+        assert_eq!(
+            render(dt, "%Y%m%dT%H%M%S %Z").unwrap(),
+            "20241228T130000 CET"
+        );
+        assert_eq!(render(dt, "http").unwrap(), "Sat, 28 Dec 2024 12:00:00 GMT");
+        assert!(render(dt, "%Q").is_err());
+        assert!(render(dt, "iso").is_err());
+    }
+}
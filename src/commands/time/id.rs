@@ -0,0 +1,340 @@
+//! Timestamps embedded in IDs (`time from-id`).
+//!
+//! The ID type is detected from its shape: decimal snowflakes, 26-character
+//! ULIDs, 27-character KSUIDs, 24-hex-digit MongoDB ObjectIds and UUIDs of
+//! version 1, 6 or 7. Twitter and Discord snowflakes look the same, so the
+//! epoch is guessed unless the kind is given.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Twitter snowflake epoch, 2010-11-04T01:42:54.657Z
+const TWITTER_EPOCH_MS: i64 = 1_288_834_974_657;
+/// Discord snowflake epoch, 2015-01-01T00:00:00Z
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+/// KSUID epoch, 2014-05-13T16:53:20Z
+const KSUID_EPOCH: i64 = 1_400_000_000;
+/// 100-nanosecond intervals from 1582-10-15 (Gregorian reform) to 1970
+const UUID_EPOCH_OFFSET: i64 = 0x01B2_1DD2_1381_4000;
+
+const CROCKFORD: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Twitter,
+    Discord,
+    Ulid,
+    Ksuid,
+    ObjectId,
+    Uuid,
+}
+
+impl Kind {
+    pub fn parse(name: &str) -> Result<Kind> {
+        match name.to_lowercase().as_str() {
+            "twitter" | "x" => Ok(Kind::Twitter),
+            "discord" => Ok(Kind::Discord),
+            "ulid" => Ok(Kind::Ulid),
+            "ksuid" => Ok(Kind::Ksuid),
+            "objectid" | "object-id" | "mongo" => Ok(Kind::ObjectId),
+            "uuid" => Ok(Kind::Uuid),
+            _ => bail!(
+                "Unknown ID kind: {}. Use twitter, discord, ulid, ksuid, objectid, or uuid.",
+                name
+            ),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Twitter => "Twitter snowflake",
+            Kind::Discord => "Discord snowflake",
+            Kind::Ulid => "ULID",
+            Kind::Ksuid => "KSUID",
+            Kind::ObjectId => "MongoDB ObjectId",
+            Kind::Uuid => "UUID v1, v6 or v7",
+        }
+    }
+}
+
+/// What an ID says about when it was made
+#[derive(Debug)]
+pub struct Decoded {
+    pub name: String,
+    pub instant: DateTime<Utc>,
+    /// Other fields packed into the ID, such as the worker or sequence
+    pub details: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Decode `id`, detecting its kind unless one is given
+pub fn decode(id: &str, kind: Option<Kind>) -> Result<Decoded> {
+    let id = id.trim();
+    let (kind, guessed) = match kind {
+        Some(kind) => (kind, false),
+        None => (detect(id)?, true),
+    };
+    let decoded = match kind {
+        Kind::Twitter | Kind::Discord => snowflake(id, kind).map(|mut decoded| {
+            if guessed {
+                decoded.note = Some(
+                    "Twitter and Discord snowflakes look alike; pass --kind twitter or --kind discord to choose the epoch"
+                        .to_string(),
+                );
+            }
+            decoded
+        }),
+        Kind::Ulid => ulid(id),
+        Kind::Ksuid => ksuid(id),
+        Kind::ObjectId => object_id(id),
+        Kind::Uuid => uuid(id),
+    };
+    decoded.ok_or_else(|| anyhow!("Not a valid {}: {}", kind.name(), id))
+}
+
+fn detect(id: &str) -> Result<Kind> {
+    let bytes = id.as_bytes();
+    let kind = if !id.is_empty() && id.len() <= 20 && bytes.iter().all(u8::is_ascii_digit) {
+        guess_snowflake(id)
+    } else if id.len() == 26 && bytes.iter().all(|b| crockford_value(*b).is_some()) {
+        Kind::Ulid
+    } else if id.len() == 27 && bytes.iter().all(u8::is_ascii_alphanumeric) {
+        Kind::Ksuid
+    } else if id.len() == 24 && bytes.iter().all(u8::is_ascii_hexdigit) {
+        Kind::ObjectId
+    } else if Uuid::try_parse(id).is_ok() {
+        Kind::Uuid
+    } else {
+        bail!(
+            "Unrecognized ID: {}. Expected a snowflake, ULID, KSUID, ObjectId, or UUID v1/v6/v7.",
+            id
+        );
+    };
+    Ok(kind)
+}
+
+/// Read as Discord unless that would put the ID in the future; the newer
+/// epoch makes recent Twitter IDs decode to years ahead
+fn guess_snowflake(id: &str) -> Kind {
+    match id.parse::<u64>() {
+        Ok(value) if ((value >> 22) as i64) + DISCORD_EPOCH_MS > Utc::now().timestamp_millis() => {
+            Kind::Twitter
+        }
+        _ => Kind::Discord,
+    }
+}
+
+fn snowflake(id: &str, kind: Kind) -> Option<Decoded> {
+    let value: u64 = id.parse().ok()?;
+    let (epoch, machine) = match kind {
+        Kind::Twitter => (TWITTER_EPOCH_MS, "datacenter"),
+        _ => (DISCORD_EPOCH_MS, "process"),
+    };
+    let millis = ((value >> 22) as i64).checked_add(epoch)?;
+    let worker = (value >> 17) & 0x1F;
+    let machine_id = (value >> 12) & 0x1F;
+    let sequence = value & 0xFFF;
+    let (worker, machine_id) = match kind {
+        // Twitter puts the datacenter above the worker; Discord the worker above the process
+        Kind::Twitter => (machine_id, worker),
+        _ => (worker, machine_id),
+    };
+
+    Some(Decoded {
+        name: kind.name().to_string(),
+        instant: DateTime::from_timestamp_millis(millis)?,
+        details: Some(format!(
+            "worker {}, {} {}, sequence {}",
+            worker, machine, machine_id, sequence
+        )),
+        note: None,
+    })
+}
+
+fn crockford_value(byte: u8) -> Option<u64> {
+    let upper = byte.to_ascii_uppercase();
+    CROCKFORD
+        .iter()
+        .position(|c| *c == upper)
+        .map(|position| position as u64)
+}
+
+fn ulid(id: &str) -> Option<Decoded> {
+    if id.len() != 26 {
+        return None;
+    }
+    // The first 10 characters hold the 48-bit millisecond timestamp
+    let millis = id.as_bytes()[..10]
+        .iter()
+        .try_fold(0u64, |acc, b| Some(acc * 32 + crockford_value(*b)?))?;
+    if millis >= 1 << 48 {
+        return None;
+    }
+    Some(Decoded {
+        name: Kind::Ulid.name().to_string(),
+        instant: DateTime::from_timestamp_millis(millis as i64)?,
+        details: None,
+        note: None,
+    })
+}
+
+fn ksuid(id: &str) -> Option<Decoded> {
+    if id.len() != 27 {
+        return None;
+    }
+    // 27 base62 digits hold 20 big-endian bytes: a 4-byte timestamp and 16 random bytes
+    let mut bytes = [0u8; 20];
+    for c in id.bytes() {
+        let mut carry = BASE62.iter().position(|b| *b == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            let value = *byte as u32 * 62 + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    let seconds = u32::from_be_bytes(bytes[..4].try_into().ok()?) as i64;
+    Some(Decoded {
+        name: Kind::Ksuid.name().to_string(),
+        instant: DateTime::from_timestamp(seconds + KSUID_EPOCH, 0)?,
+        details: None,
+        note: None,
+    })
+}
+
+fn object_id(id: &str) -> Option<Decoded> {
+    if id.len() != 24 || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let seconds = i64::from_str_radix(&id[..8], 16).ok()?;
+    let counter = u32::from_str_radix(&id[18..], 16).ok()?;
+    Some(Decoded {
+        name: Kind::ObjectId.name().to_string(),
+        instant: DateTime::from_timestamp(seconds, 0)?,
+        details: Some(format!("random {}, counter {}", &id[8..18], counter)),
+        note: None,
+    })
+}
+
+fn uuid(id: &str) -> Option<Decoded> {
+    let uuid = Uuid::try_parse(id).ok()?;
+    let bytes = uuid.as_bytes();
+    let be = |range: std::ops::Range<usize>| {
+        bytes[range]
+            .iter()
+            .fold(0i64, |acc, b| (acc << 8) | *b as i64)
+    };
+
+    let version = uuid.get_version_num();
+    let instant = match version {
+        7 => DateTime::from_timestamp_millis(be(0..6))?,
+        1 | 6 => {
+            let ticks = if version == 1 {
+                // time_low, time_mid, time_hi (without the version nibble)
+                ((be(6..8) & 0x0FFF) << 48) | (be(4..6) << 32) | be(0..4)
+            } else {
+                // time_high, time_mid, time_low: already in sort order
+                (be(0..6) << 12) | (be(6..8) & 0x0FFF)
+            };
+            let hundred_nanos = ticks - UUID_EPOCH_OFFSET;
+            let seconds = hundred_nanos.div_euclid(10_000_000);
+            let nanos = hundred_nanos.rem_euclid(10_000_000) * 100;
+            DateTime::from_timestamp(seconds, nanos as u32)?
+        }
+        _ => return None,
+    };
+    let details = matches!(version, 1 | 6).then(|| {
+        let node: Vec<String> = bytes[10..].iter().map(|b| format!("{:02x}", b)).collect();
+        format!(
+            "clock sequence {}, node {}",
+            be(8..10) & 0x3FFF,
+            node.join(":")
+        )
+    });
+
+    Some(Decoded {
+        name: format!("UUID v{}", version),
+        instant,
+        details,
+        note: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iso(id: &str, kind: Option<Kind>) -> String {
+        decode(id, kind)
+            .unwrap()
+            .instant
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+
+    #[test]
+    fn test_detect_and_decode() {
+        assert_eq!(
+            iso("01ARZ3NDEKTSV4RRFFQ69G5FAV", None),
+            "2016-07-30T23:54:10.259Z"
+        );
+        assert_eq!(
+            iso("0ujtsYcgvSTl8PAuAdqWYSMnLOv", None),
+            "2017-10-10T04:00:47.000Z"
+        );
+        assert_eq!(
+            iso("507f1f77bcf86cd799439011", None),
+            "2012-10-17T21:13:27.000Z"
+        );
+        assert_eq!(
+            iso("017f22e2-79b0-7cc3-98c4-dc0c0c07398f", None),
+            "2022-02-22T19:22:22.000Z"
+        );
+        assert_eq!(
+            iso("C232AB00-9414-11EC-B3C8-9F6BDECED846", None),
+            "2022-02-22T19:22:22.000Z"
+        );
+        assert_eq!(
+            iso("1EC9414C-232A-6B00-B3C8-9F6BDECED846", None),
+            "2022-02-22T19:22:22.000Z"
+        );
+    }
+
+    #[test]
+    fn test_snowflakes() {
+        // Discord's documentation example
+        let discord = decode("175928847299117063", None).unwrap();
+        assert_eq!(discord.name, "Discord snowflake");
+        assert_eq!(
+            discord
+                .instant
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "2016-04-30T11:18:25.796Z"
+        );
+        assert_eq!(
+            discord.details.as_deref(),
+            Some("worker 1, process 0, sequence 7")
+        );
+
+        // A 2024 tweet would land years ahead with Discord's epoch
+        let twitter = decode("1872000000000000000", None).unwrap();
+        assert_eq!(twitter.name, "Twitter snowflake");
+        assert!(twitter.instant.to_rfc3339().starts_with("2024-12-25"));
+        assert_eq!(
+            iso("175928847299117063", Some(Kind::Twitter)),
+            "2012-03-03T13:01:20.453Z"
+        );
+    }
+
+    #[test]
+    fn test_rejects() {
+        // UUID v4 carries no time
+        assert!(decode("7e45020d-bd95-455b-944c-3a803ed31b8b", None).is_err());
+        assert!(decode("not-an-id", None).is_err());
+        assert!(decode("zzzzzzzzzzzzzzzzzzzzzzzzzz", None).is_err());
+        assert!(decode("507f1f77bcf86cd799439011", Some(Kind::Ulid)).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! Date parsing for `time to-unix`.
+//!
+//! Accepts absolute timestamps (RFC 3339, RFC 2822, HTTP-date, common log
+//! format and a few `strftime` layouts) as well as relative expressions such
+//! as `now`, `+3d`, `2 hours ago`, `in 3 days`, `yesterday` and
+//! `next monday 09:00`. Dates without an offset, and calendar words like
+//! `today`, are read as wall-clock time in the requested zone.
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use super::duration::{self, Span};
+
+/// Layouts without an offset, interpreted in the requested zone
+const NAIVE_FORMATS: [&str; 7] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%d-%m-%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M:%S",
+];
+
+/// Obsolete HTTP-date forms (RFC 9110 section 5.6.7), always GMT
+const HTTP_FORMATS: [&str; 2] = ["%A, %d-%b-%y %H:%M:%S GMT", "%a %b %e %H:%M:%S %Y"];
+
+const DATE_FORMATS: [&str; 2] = ["%Y-%m-%d", "%Y/%m/%d"];
+
+/// A parsed date, with a note when its wall-clock time was ambiguous or
+/// skipped in the zone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parsed {
+    pub instant: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+/// Either a fixed instant or a wall-clock time still to be placed in a zone
+enum Moment {
+    Instant(DateTime<Utc>),
+    Local(NaiveDateTime),
+    /// Already placed in the zone, e.g. after moving by calendar months
+    Placed(Parsed),
+}
+
+/// Parse an absolute or relative date, resolving relative ones against `now`
+/// and wall-clock times in `tz`
+pub fn parse(input: &str, now: DateTime<Utc>, tz: Tz) -> Option<Parsed> {
+    let text = input.trim();
+    let moment = parse_absolute(text)
+        .or_else(|| parse_relative(&text.to_lowercase(), now.with_timezone(&tz)))?;
+
+    match moment {
+        Moment::Instant(instant) => Some(Parsed {
+            instant,
+            note: None,
+        }),
+        Moment::Local(naive) => Some(resolve(naive, tz)),
+        Moment::Placed(parsed) => Some(parsed),
+    }
+}
+
+/// Place a wall-clock time in `tz`, explaining DST folds and gaps
+///
+/// Ambiguous times take the earlier instant. Times skipped by a gap are read
+/// with the offset in force before it, which moves them forward.
+pub fn resolve(naive: NaiveDateTime, tz: Tz) -> Parsed {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) => Parsed {
+            instant: dt.to_utc(),
+            note: None,
+        },
+        LocalResult::Ambiguous(earlier, later) => Parsed {
+            instant: earlier.to_utc(),
+            note: Some(format!(
+                "{} occurs twice in {} ({} and {}); using the earlier",
+                naive,
+                tz,
+                earlier.format("%Z %:z"),
+                later.format("%Z %:z")
+            )),
+        },
+        LocalResult::None => {
+            let before = tz
+                .offset_from_utc_datetime(&(naive - Duration::days(1)))
+                .fix();
+            let instant = Utc.from_utc_datetime(&(naive - before));
+            Parsed {
+                instant,
+                note: Some(format!(
+                    "{} does not exist in {} (skipped by a DST change); using {}",
+                    naive,
+                    tz,
+                    instant.with_timezone(&tz).format("%Y-%m-%d %H:%M:%S %Z")
+                )),
+            }
+        }
+    }
+}
+
+fn parse_absolute(text: &str) -> Option<Moment> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some(Moment::Instant(dt.to_utc()));
+    }
+    // Also covers the preferred HTTP-date form, `Sat, 28 Dec 2024 12:00:00 GMT`
+    if let Ok(dt) = DateTime::parse_from_rfc2822(text) {
+        return Some(Moment::Instant(dt.to_utc()));
+    }
+
+    // Common log format, with or without the surrounding brackets
+    let clf = text.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(dt) = DateTime::parse_from_str(clf, "%d/%b/%Y:%H:%M:%S %z") {
+        return Some(Moment::Instant(dt.to_utc()));
+    }
+
+    for fmt in HTTP_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(text, fmt) {
+            return Some(Moment::Instant(Utc.from_utc_datetime(&naive)));
+        }
+    }
+    for fmt in NAIVE_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(text, fmt) {
+            return Some(Moment::Local(naive));
+        }
+    }
+    for fmt in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(text, fmt) {
+            return Some(Moment::Local(date.and_time(NaiveTime::MIN)));
+        }
+    }
+    None
+}
+
+fn parse_relative(text: &str, now: DateTime<Tz>) -> Option<Moment> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let today = now.date_naive();
+    let tz = now.timezone();
+    let now = now.to_utc();
+
+    match words.as_slice() {
+        ["now"] => Some(Moment::Instant(now)),
+        [day @ ("today" | "yesterday" | "tomorrow"), rest @ ..] => {
+            let offset = match *day {
+                "yesterday" => -1,
+                "tomorrow" => 1,
+                _ => 0,
+            };
+            at_time(today + Duration::days(offset), rest)
+        }
+        [dir @ ("next" | "last"), what, rest @ ..] => {
+            let step = if *dir == "next" { 1 } else { -1 };
+            match parse_weekday(what) {
+                Some(weekday) => {
+                    let date = (1..=7)
+                        .map(|d| today + Duration::days(d * step))
+                        .find(|d| d.weekday() == weekday)?;
+                    at_time(date, rest)
+                }
+                None if rest.is_empty() => shift(now, step, what, tz).map(Moment::Placed),
+                None => None,
+            }
+        }
+        ["in", amount, unit] => shift(now, parse_amount(amount)?, unit, tz).map(Moment::Placed),
+        [amount, unit, "ago"] => shift(now, -parse_amount(amount)?, unit, tz).map(Moment::Placed),
+        [offset] => parse_offset(offset, now, tz).map(Moment::Placed),
+        _ => None,
+    }
+}
+
+/// `date` at midnight, or at the time given as `09:00`, `09:00:30` or `at 09:00`
+fn at_time(date: NaiveDate, rest: &[&str]) -> Option<Moment> {
+    let time = match rest {
+        [] => NaiveTime::MIN,
+        [time] | ["at", time] => NaiveTime::parse_from_str(time, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+            .ok()?,
+        _ => return None,
+    };
+    Some(Moment::Local(date.and_time(time)))
+}
+
+fn parse_amount(word: &str) -> Option<i64> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        _ => word.parse().ok(),
+    }
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Compact offsets like `+3d`, `-2h` or `+1h30m`
+fn parse_offset(text: &str, now: DateTime<Utc>, tz: Tz) -> Option<Parsed> {
+    if !text.starts_with(['+', '-']) {
+        return None;
+    }
+    duration::parse(text)?.apply(now, tz)
+}
+
+/// Move `from` by `amount` units; months and years follow the calendar in `tz`
+fn shift(from: DateTime<Utc>, amount: i64, unit: &str, tz: Tz) -> Option<Parsed> {
+    Span::of(amount, unit)?.apply(from, tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2024-12-25 10:30:00 UTC
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, 25, 10, 30, 0).unwrap()
+    }
+
+    fn at(input: &str) -> String {
+        parse(input, now(), Tz::UTC)
+            .unwrap_or_else(|| panic!("failed to parse {:?}", input))
+            .instant
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+
+    #[test]
+    fn test_absolute_formats() {
+        let expected = "2024-12-28 12:00:00";
+        for input in [
+            "2024-12-28T12:00:00Z",
+            "2024-12-28T13:00:00+01:00",
+            "Sat, 28 Dec 2024 12:00:00 +0000",
+            "Sat, 28 Dec 2024 12:00:00 GMT",
+            "Saturday, 28-Dec-24 12:00:00 GMT",
+            "Sat Dec 28 12:00:00 2024",
+            "28/Dec/2024:14:00:00 +0200",
+            "[28/Dec/2024:12:00:00 +0000]",
+            "2024-12-28 12:00:00",
+            "2024/12/28 12:00:00",
+        ] {
+            assert_eq!(at(input), expected, "{}", input);
+        }
+        assert_eq!(at("2024-12-28"), "2024-12-28 00:00:00");
+    }
+
+    #[test]
+    fn test_relative_days() {
+        assert_eq!(at("now"), "2024-12-25 10:30:00");
+        assert_eq!(at("today"), "2024-12-25 00:00:00");
+        assert_eq!(at("Yesterday"), "2024-12-24 00:00:00");
+        assert_eq!(at("tomorrow 18:15"), "2024-12-26 18:15:00");
+        assert_eq!(at("next monday 09:00"), "2024-12-30 09:00:00");
+        assert_eq!(at("next wednesday"), "2025-01-01 00:00:00");
+        assert_eq!(at("last friday at 17:00"), "2024-12-20 17:00:00");
+    }
+
+    #[test]
+    fn test_relative_offsets() {
+        assert_eq!(at("+3d"), "2024-12-28 10:30:00");
+        assert_eq!(at("-1h30m"), "2024-12-25 09:00:00");
+        assert_eq!(at("2 hours ago"), "2024-12-25 08:30:00");
+        assert_eq!(at("in 3 days"), "2024-12-28 10:30:00");
+        assert_eq!(at("an hour ago"), "2024-12-25 09:30:00");
+        assert_eq!(at("+1mo"), "2025-01-25 10:30:00");
+        assert_eq!(at("next week"), "2025-01-01 10:30:00");
+        assert_eq!(at("1 year ago"), "2023-12-25 10:30:00");
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        for input in ["", "soon", "+", "+3x", "next blursday", "3 parsecs ago"] {
+            assert!(parse(input, now(), Tz::UTC).is_none(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_zone_wall_clock() {
+        let tokyo = parse("2024-12-28 12:00:00", now(), Tz::Asia__Tokyo).unwrap();
+        assert_eq!(tokyo.instant.to_rfc3339(), "2024-12-28T03:00:00+00:00");
+        // Offsets in the input win over the zone
+        let fixed = parse("2024-12-28T12:00:00Z", now(), Tz::Asia__Tokyo).unwrap();
+        assert_eq!(fixed.instant.to_rfc3339(), "2024-12-28T12:00:00+00:00");
+        // It is already Dec 26 in Kiritimati (+14:00) at 10:30 UTC on Dec 25
+        let today = parse("today", now(), Tz::Pacific__Kiritimati).unwrap();
+        assert_eq!(today.instant.to_rfc3339(), "2024-12-25T10:00:00+00:00");
+    }
+
+    #[test]
+    fn test_dst_fold_and_gap() {
+        let ny = Tz::America__New_York;
+        let fold = parse("2024-11-03 01:30:00", now(), ny).unwrap();
+        assert_eq!(fold.instant.to_rfc3339(), "2024-11-03T05:30:00+00:00");
+        assert!(fold.note.unwrap().contains("occurs twice"));
+
+        let gap = parse("2024-03-10 02:30:00", now(), ny).unwrap();
+        assert_eq!(gap.instant.to_rfc3339(), "2024-03-10T07:30:00+00:00");
+        assert!(gap.note.unwrap().contains("03:30:00 EDT"));
+    }
+}
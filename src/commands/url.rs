@@ -1,86 +1,502 @@
-use anyhow::Result;
-
-/// URL encode a string
-pub fn encode(input: &str) -> Result<String> {
-    let encoded: String = input
-        .chars()
-        .map(|c| {
-            match c {
-                // Unreserved characters (RFC 3986)
-                'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => {
-                    c.to_string()
-                }
-                // Everything else gets percent-encoded
-                _ => {
-                    let mut buf = [0; 4];
-                    let bytes = c.encode_utf8(&mut buf);
-                    bytes
-                        .bytes()
-                        .map(|b| format!("%{:02X}", b))
-                        .collect::<String>()
-                }
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+use super::input::Source;
+use super::Output;
+use parse::HostKind;
+
+mod idn;
+mod normalize;
+mod parse;
+mod query;
+
+/// The part of a URL a string is encoded for. Each keeps a different set of
+/// characters as they are (RFC 3986 section 3; WHATWG URL for forms).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Component {
+    /// A single value: only unreserved characters are kept
+    Value,
+    Path,
+    /// One path segment: like `Path`, but `/` is encoded
+    Segment,
+    Query,
+    Fragment,
+    Userinfo,
+    /// application/x-www-form-urlencoded, with spaces as `+`
+    Form,
+    /// A whole URL: delimiters and existing escapes are kept
+    Full,
+}
+
+impl Component {
+    pub fn parse(name: &str) -> Result<Component> {
+        match name.to_lowercase().as_str() {
+            "value" | "component" => Ok(Component::Value),
+            "path" => Ok(Component::Path),
+            "segment" => Ok(Component::Segment),
+            "query" => Ok(Component::Query),
+            "fragment" => Ok(Component::Fragment),
+            "userinfo" => Ok(Component::Userinfo),
+            "form" => Ok(Component::Form),
+            "full" | "url" => Ok(Component::Full),
+            _ => bail!(
+                "Unknown component: {}. Use value, path, segment, query, fragment, userinfo, form, or full.",
+                name
+            ),
+        }
+    }
+
+    /// Reserved characters allowed unencoded in this component
+    fn reserved(self) -> &'static str {
+        match self {
+            Component::Value | Component::Form => "",
+            Component::Path => "!$&'()*+,;=:@/",
+            Component::Segment => "!$&'()*+,;=:@",
+            Component::Query | Component::Fragment => "!$&'()*+,;=:@/?",
+            Component::Userinfo => "!$&'()*+,;=:",
+            Component::Full => "!$&'()*+,;=:@/?#[]",
+        }
+    }
+}
+
+/// URL encode a string for `component`
+pub fn encode(input: &str, component: Component) -> Result<String> {
+    let bytes = input.as_bytes();
+    let mut encoded = String::with_capacity(input.len());
+
+    for (i, c) in input.char_indices() {
+        let keep = match c {
+            // Unreserved characters (RFC 3986); forms escape '~' but not '*'
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' => true,
+            '~' => component != Component::Form,
+            '*' if component == Component::Form => true,
+            ' ' if component == Component::Form => {
+                encoded.push('+');
+                continue;
             }
-        })
-        .collect();
-    
+            // Don't double-encode escapes already in a full URL
+            '%' => component == Component::Full && is_escape(bytes, i),
+            _ => component.reserved().contains(c),
+        };
+        if keep {
+            encoded.push(c);
+        } else {
+            // Everything else gets percent-encoded
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+
     Ok(encoded)
 }
 
-/// URL decode a string
-pub fn decode(input: &str) -> Result<String> {
-    let mut result = String::with_capacity(input.len());
-    let mut chars = input.chars().peekable();
-    
-    while let Some(c) = chars.next() {
-        if c == '%' {
-            // Collect hex digits
-            let hex: String = chars.by_ref().take(2).collect();
-            if hex.len() == 2 {
-                if let Ok(byte) = u8::from_str_radix(&hex, 16) {
-                    // Handle multi-byte UTF-8 sequences
-                    let mut bytes = vec![byte];
-                    
-                    // Check for continuation bytes
-                    while chars.peek() == Some(&'%') {
-                        let pos = chars.clone().skip(1).take(2).collect::<String>();
-                        if let Ok(next_byte) = u8::from_str_radix(&pos, 16) {
-                            if next_byte & 0xC0 == 0x80 {
-                                // It's a continuation byte
-                                chars.next(); // consume '%'
-                                chars.next(); // consume first hex
-                                chars.next(); // consume second hex
-                                bytes.push(next_byte);
-                            } else {
-                                break;
-                            }
-                        } else {
-                            break;
-                        }
-                    }
-                    
-                    if let Ok(s) = String::from_utf8(bytes) {
-                        result.push_str(&s);
+fn is_escape(bytes: &[u8], at: usize) -> bool {
+    bytes.get(at + 1).is_some_and(u8::is_ascii_hexdigit)
+        && bytes.get(at + 2).is_some_and(u8::is_ascii_hexdigit)
+}
+
+/// URL decode a string; `form` also turns `+` into a space, as in
+/// application/x-www-form-urlencoded data. Malformed escapes and bytes that
+/// are not UTF-8 are kept as escape text, or reported with `strict`.
+pub fn decode(input: &str, form: bool, strict: bool) -> Result<String> {
+    let (bytes, offsets, mut problems) = unescape(input, form);
+    let (text, utf8_problems) = to_text(&bytes, &offsets);
+    problems.extend(utf8_problems);
+    problems.sort_by_key(|p| p.offset);
+    if strict {
+        report(problems)?;
+    }
+    Ok(text)
+}
+
+/// Decode to raw bytes on stdout, for payloads that are not UTF-8 text
+pub fn decode_bytes(input: &str, form: bool, strict: bool) -> Result<Output> {
+    let bytes = decode_raw(input, form, strict)?;
+    let mut out = io::stdout().lock();
+    out.write_all(&bytes)?;
+    out.flush()?;
+    Ok(Output::Written)
+}
+
+fn decode_raw(input: &str, form: bool, strict: bool) -> Result<Vec<u8>> {
+    let (bytes, _, problems) = unescape(input, form);
+    if strict {
+        report(problems)?;
+    }
+    Ok(bytes)
+}
+
+/// Lenient decoding for display
+fn percent_decode(input: &str, plus_as_space: bool) -> String {
+    let (bytes, offsets, _) = unescape(input, plus_as_space);
+    to_text(&bytes, &offsets).0
+}
+
+/// Something `--strict` rejects, at a byte offset in the input
+#[derive(Debug, PartialEq)]
+struct Problem {
+    offset: usize,
+    message: String,
+}
+
+fn report(problems: Vec<Problem>) -> Result<()> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = problems
+        .iter()
+        .map(|p| format!("  offset {}: {}", p.offset, p.message))
+        .collect();
+    bail!(
+        "{} malformed escape{}:\n{}",
+        problems.len(),
+        if problems.len() == 1 { "" } else { "s" },
+        lines.join("\n")
+    );
+}
+
+/// Decode escapes to bytes, recording the input offset of every byte and
+/// keeping malformed escapes as they are
+fn unescape(input: &str, plus_as_space: bool) -> (Vec<u8>, Vec<usize>, Vec<Problem>) {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut offsets = Vec::with_capacity(bytes.len());
+    let mut problems = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if is_escape(bytes, i) => {
+                let hex = &input[i + 1..i + 3];
+                out.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                offsets.push(i);
+                i += 3;
+                continue;
+            }
+            b'%' => {
+                let text: String = input[i..].chars().take(3).collect();
+                let truncated = text.len() < 3 && text[1..].bytes().all(|b| b.is_ascii_hexdigit());
+                problems.push(Problem {
+                    offset: i,
+                    message: if truncated {
+                        format!("{} is truncated", text)
                     } else {
-                        result.push('%');
-                        result.push_str(&hex);
-                    }
-                } else {
-                    result.push('%');
-                    result.push_str(&hex);
-                }
-            } else {
-                result.push('%');
-                result.push_str(&hex);
+                        format!("{} is not a valid escape", text)
+                    },
+                });
+                out.push(b'%');
+            }
+            // Handle + as space (form data)
+            b'+' if plus_as_space => out.push(b' '),
+            b => out.push(b),
+        }
+        offsets.push(i);
+        i += 1;
+    }
+    (out, offsets, problems)
+}
+
+/// Decoded bytes as text; sequences that are not UTF-8 (which can only come
+/// from escapes) are written back as escapes
+fn to_text(bytes: &[u8], offsets: &[usize]) -> (String, Vec<Problem>) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut problems = Vec::new();
+    let mut at = 0;
+
+    loop {
+        match std::str::from_utf8(&bytes[at..]) {
+            Ok(valid) => {
+                text.push_str(valid);
+                break;
+            }
+            Err(err) => {
+                let valid_end = at + err.valid_up_to();
+                text.push_str(std::str::from_utf8(&bytes[at..valid_end]).unwrap_or_default());
+                let bad_end = valid_end + err.error_len().unwrap_or(bytes.len() - valid_end);
+                let escaped: String = bytes[valid_end..bad_end]
+                    .iter()
+                    .map(|b| format!("%{:02X}", b))
+                    .collect();
+                text.push_str(&escaped);
+                problems.push(Problem {
+                    offset: offsets[valid_end],
+                    message: format!("{} is not valid UTF-8", escaped),
+                });
+                at = bad_end;
+            }
+        }
+    }
+    (text, problems)
+}
+
+/// Break a URL into its components, decoding each for display
+pub fn parse(input: &str, as_json: bool) -> Result<String> {
+    let url = parse::parse(input.trim())?;
+    let authority = url.authority.as_ref();
+    let host = authority.map(|a| a.host.as_str());
+    let host_kind = host.map(HostKind::of);
+    let (host_ascii, host_unicode) = match (host, host_kind) {
+        (Some(host), Some(HostKind::Domain)) if !host.is_empty() => {
+            let ascii = idn::to_ascii(&percent_decode(host, false))?;
+            let unicode = idn::to_unicode(&ascii)?;
+            (Some(ascii), Some(unicode))
+        }
+        _ => (None, None),
+    };
+    let warnings = host_unicode
+        .as_deref()
+        .map(idn::warnings)
+        .unwrap_or_default();
+    let port = match authority.and_then(|a| a.port.as_deref()) {
+        Some(port) if !port.is_empty() => port.parse::<u16>().ok(),
+        _ => None,
+    };
+    let segments: Vec<String> = url
+        .segments()
+        .iter()
+        .map(|s| percent_decode(s, false))
+        .collect();
+    let params = url.query.as_deref().map(query_pairs).unwrap_or_default();
+    let userinfo = authority
+        .and_then(|a| a.userinfo.as_deref())
+        .map(|u| percent_decode(u, false));
+    let fragment = url.fragment.as_deref().map(|f| percent_decode(f, false));
+
+    if as_json {
+        let value = json!({
+            "scheme": url.scheme,
+            "userinfo": userinfo,
+            "host": host,
+            "host_type": host_kind.map(HostKind::name),
+            "host_ascii": host_ascii,
+            "host_unicode": host_unicode,
+            "host_warnings": warnings,
+            "port": port,
+            "path": url.path,
+            "segments": segments,
+            "query": url.query,
+            "params": params
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": value }))
+                .collect::<Vec<Value>>(),
+            "fragment": fragment,
+        });
+        return Ok(serde_json::to_string_pretty(&value)?);
+    }
+
+    let mut lines = Vec::new();
+    if let Some(scheme) = &url.scheme {
+        lines.push(format!("Scheme:   {}", scheme));
+    }
+    if let Some(userinfo) = &userinfo {
+        lines.push(format!("Userinfo: {}", userinfo));
+    }
+    if let (Some(host), Some(kind)) = (host, host_kind) {
+        lines.push(format!("Host:     {} ({})", host, kind.name()));
+        match (&host_ascii, &host_unicode) {
+            (Some(ascii), _) if ascii != host && !host.is_ascii() => {
+                lines.push(format!("ASCII:    {}", ascii))
             }
-        } else if c == '+' {
-            // Handle + as space (common in query strings)
-            result.push(' ');
+            (_, Some(unicode)) if !unicode.eq_ignore_ascii_case(host) => {
+                lines.push(format!("Unicode:  {}", unicode))
+            }
+            _ => {}
+        }
+        for warning in &warnings {
+            lines.push(format!("Warning:  {}", warning));
+        }
+    }
+    match (port, url.scheme.as_deref().and_then(parse::default_port)) {
+        (Some(port), _) => lines.push(format!("Port:     {}", port)),
+        (None, Some(default)) if authority.is_some() => {
+            lines.push(format!("Port:     {} (default)", default))
+        }
+        _ => {}
+    }
+    lines.push(format!(
+        "Path:     {}",
+        if url.path.is_empty() {
+            "(empty)"
         } else {
-            result.push(c);
+            &url.path
+        }
+    ));
+    for (i, segment) in segments.iter().enumerate() {
+        lines.push(format!("  {}. {}", i + 1, segment));
+    }
+    if let Some(query) = &url.query {
+        lines.push(format!("Query:    {}", query));
+        for (key, value) in &params {
+            lines.push(format!("  {} = {}", key, value));
+        }
+    }
+    if let Some(fragment) = &fragment {
+        lines.push(format!("Fragment: {}", fragment));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Convert an internationalized domain to its ASCII (Punycode) form; spoofing
+/// warnings go to stderr
+pub fn idn_to_ascii(domain: &str) -> Result<String> {
+    let ascii = idn::to_ascii(domain)?;
+    for warning in idn::warnings(&idn::to_unicode(&ascii)?) {
+        eprintln!("Warning: {}", warning);
+    }
+    Ok(ascii)
+}
+
+/// Convert a Punycode domain to Unicode; spoofing warnings go to stderr
+pub fn idn_to_unicode(domain: &str) -> Result<String> {
+    let unicode = idn::to_unicode(domain)?;
+    for warning in idn::warnings(&unicode) {
+        eprintln!("Warning: {}", warning);
+    }
+    Ok(unicode)
+}
+
+/// Append path segments and `key=value` query parameters to `base`, encoding
+/// each piece for where it goes
+pub fn build(
+    base: &str,
+    segments: &[String],
+    params: &[String],
+    fragment: Option<&str>,
+) -> Result<String> {
+    let mut url = parse::parse(base.trim())?;
+
+    for segment in segments {
+        if !url.path.ends_with('/') && (url.authority.is_some() || !url.path.is_empty()) {
+            url.path.push('/');
+        }
+        url.path.push_str(&encode(segment, Component::Segment)?);
+    }
+
+    let mut pairs = raw_pairs(url.query.as_deref());
+    for param in params {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        pairs.push(encode_pair(key, value)?);
+    }
+    set_pairs(&mut url, pairs);
+
+    if let Some(fragment) = fragment {
+        url.fragment = Some(encode(fragment, Component::Fragment)?);
+    }
+    Ok(url.to_string())
+}
+
+/// Set query parameter `key` to `value`, replacing every existing value in
+/// place of the first one, or appending it
+pub fn set_query(url: &str, key: &str, value: &str) -> Result<String> {
+    let mut url = parse::parse(url.trim())?;
+    let pair = encode_pair(key, value)?;
+    let mut pairs = Vec::new();
+    let mut replaced = false;
+
+    for raw in raw_pairs(url.query.as_deref()) {
+        if pair_key(&raw) != key {
+            pairs.push(raw);
+        } else if !replaced {
+            pairs.push(pair.clone());
+            replaced = true;
         }
     }
-    
-    Ok(result)
+    if !replaced {
+        pairs.push(pair);
+    }
+    set_pairs(&mut url, pairs);
+    Ok(url.to_string())
+}
+
+/// Remove every query parameter named `key`
+pub fn remove_query(url: &str, key: &str) -> Result<String> {
+    let mut url = parse::parse(url.trim())?;
+    let pairs = raw_pairs(url.query.as_deref())
+        .into_iter()
+        .filter(|raw| pair_key(raw) != key)
+        .collect();
+    set_pairs(&mut url, pairs);
+    Ok(url.to_string())
+}
+
+fn encode_pair(key: &str, value: &str) -> Result<String> {
+    Ok(format!(
+        "{}={}",
+        encode(key, Component::Value)?,
+        encode(value, Component::Value)?
+    ))
+}
+
+/// Query parameters as written, skipping empty ones
+fn raw_pairs(query: Option<&str>) -> Vec<String> {
+    query
+        .unwrap_or("")
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn pair_key(raw: &str) -> String {
+    percent_decode(raw.split_once('=').map_or(raw, |(key, _)| key), true)
+}
+
+/// Replace the query, dropping the `?` when no parameters are left
+fn set_pairs(url: &mut parse::Url, pairs: Vec<String>) {
+    url.query = (!pairs.is_empty()).then(|| pairs.join("&"));
+}
+
+/// Decode a query string, or the query of a URL, into nested JSON
+pub fn query_to_json(input: &str) -> Result<String> {
+    let text = input.trim();
+    // Only a `?` before any parameter starts the query of a URL
+    let query = match text.find('?') {
+        Some(question) if !text[..question].contains(['=', '&']) => &text[question + 1..],
+        _ => text,
+    };
+    let query = query.split('#').next().unwrap_or_default();
+    let value = query::to_json(&query_pairs(query))?;
+    Ok(serde_json::to_string_pretty(&value)?)
+}
+
+/// Flatten a JSON object into a query string; `arrays` is brackets, index
+/// or repeat
+pub fn json_to_query(input: Source<'_>, arrays: &str) -> Result<String> {
+    let style = query::ArrayStyle::parse(arrays)?;
+    let value = super::json::parse_input(input)?;
+    let pairs = query::from_json(&value, style)?
+        .iter()
+        .map(|(key, value)| encode_pair(key, value))
+        .collect::<Result<Vec<String>>>()?;
+    Ok(pairs.join("&"))
+}
+
+/// Normalize a URL (RFC 3986 section 6) so equivalent URLs compare equal
+pub fn normalize(input: &str, sort_query: bool) -> Result<String> {
+    let url = parse::parse(input.trim())?;
+    Ok(normalize::normalize(url, sort_query).to_string())
+}
+
+/// Resolve a relative reference such as `../img/a.png` against a base URL
+pub fn resolve(base: &str, reference: &str) -> Result<String> {
+    let base = parse::parse(base.trim()).context("Invalid base URL")?;
+    let reference = parse::parse(reference.trim()).context("Invalid reference")?;
+    Ok(normalize::resolve(&base, &reference)?.to_string())
+}
+
+/// Decoded `key=value` pairs in order, keeping repeated keys
+fn query_pairs(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key, true), percent_decode(value, true))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -89,37 +505,231 @@ mod tests {
 
     #[test]
     fn test_encode_simple() {
-        assert_eq!(encode("hello world").unwrap(), "hello%20world");
+        assert_eq!(
+            encode("hello world", Component::Value).unwrap(),
+            "hello%20world"
+        );
     }
 
     #[test]
     fn test_encode_special() {
-        assert_eq!(encode("a=b&c=d").unwrap(), "a%3Db%26c%3Dd");
+        assert_eq!(
+            encode("a=b&c=d", Component::Value).unwrap(),
+            "a%3Db%26c%3Dd"
+        );
+    }
+
+    #[test]
+    fn test_encode_components() {
+        let cases = [
+            (
+                Component::Path,
+                "/docs/a b/ü?.md",
+                "/docs/a%20b/%C3%BC%3F.md",
+            ),
+            (Component::Segment, "a/b:c", "a%2Fb:c"),
+            (Component::Query, "q=a b&lang=en/us?", "q=a%20b&lang=en/us?"),
+            (Component::Fragment, "sec 1#2", "sec%201%232"),
+            (Component::Userinfo, "bob:p@ss/w", "bob:p%40ss%2Fw"),
+            (Component::Form, "a b~*&c", "a+b%7E*%26c"),
+            (
+                Component::Full,
+                "https://example.com/a b/%C3%BC?q=100%#top",
+                "https://example.com/a%20b/%C3%BC?q=100%25#top",
+            ),
+        ];
+        for (component, input, expected) in cases {
+            assert_eq!(
+                encode(input, component).unwrap(),
+                expected,
+                "{:?}",
+                component
+            );
+        }
+        assert!(Component::parse("nope").is_err());
     }
 
     #[test]
     fn test_decode_simple() {
-        assert_eq!(decode("hello%20world").unwrap(), "hello world");
+        assert_eq!(
+            decode("hello%20world", false, false).unwrap(),
+            "hello world"
+        );
     }
 
     #[test]
     fn test_decode_plus() {
-        assert_eq!(decode("hello+world").unwrap(), "hello world");
+        // `+` is only a space in form data; in paths it is a literal plus
+        assert_eq!(decode("hello+world", false, false).unwrap(), "hello+world");
+        assert_eq!(decode("hello+world", true, false).unwrap(), "hello world");
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        let input = "a%G1b%E2%82%AC%C3(%4";
+        // Lenient decoding keeps what it cannot decode as escape text
+        assert_eq!(decode(input, false, false).unwrap(), "a%G1b€%C3(%4");
+
+        let err = decode(input, false, true).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "3 malformed escapes:\n  \
+             offset 1: %G1 is not a valid escape\n  \
+             offset 14: %C3 is not valid UTF-8\n  \
+             offset 18: %4 is truncated"
+        );
+    }
+
+    #[test]
+    fn test_decode_bytes() {
+        assert_eq!(decode_raw("%FF%00a+b", true, true).unwrap(), b"\xff\x00a b");
+        assert!(decode_raw("%ZZ", false, true).is_err());
     }
 
     #[test]
     fn test_roundtrip() {
         let original = "Hello World! @#$%^&*()";
-        let encoded = encode(original).unwrap();
-        let decoded = decode(&encoded).unwrap();
+        let encoded = encode(original, Component::Value).unwrap();
+        let decoded = decode(&encoded, false, false).unwrap();
         assert_eq!(decoded, original);
+
+        let encoded = encode(original, Component::Form).unwrap();
+        assert_eq!(decode(&encoded, true, false).unwrap(), original);
+    }
+
+    #[test]
+    fn test_build() {
+        let url = build(
+            "https://x/api",
+            &["v1".into(), "a b/c".into()],
+            &[
+                "k=v".into(),
+                "list=1".into(),
+                "list=2".into(),
+                "q=a&b=c".into(),
+            ],
+            Some("sec 1"),
+        )
+        .unwrap();
+        assert_eq!(
+            url,
+            "https://x/api/v1/a%20b%2Fc?k=v&list=1&list=2&q=a%26b%3Dc#sec%201"
+        );
+
+        assert_eq!(
+            build("https://x", &["v1".into()], &[], None).unwrap(),
+            "https://x/v1"
+        );
+        assert_eq!(
+            build("https://x/?a=1", &["v1".into()], &["b=2".into()], None).unwrap(),
+            "https://x/v1?a=1&b=2"
+        );
+    }
+
+    #[test]
+    fn test_edit_query() {
+        let url = "https://x/?a=1&b=2&a=3#top";
+        assert_eq!(
+            set_query(url, "a", "x y").unwrap(),
+            "https://x/?a=x%20y&b=2#top"
+        );
+        assert_eq!(
+            set_query(url, "c", "&").unwrap(),
+            "https://x/?a=1&b=2&a=3&c=%26#top"
+        );
+        assert_eq!(remove_query(url, "a").unwrap(), "https://x/?b=2#top");
+        assert_eq!(
+            remove_query("https://x/?a+b=1", "a b").unwrap(),
+            "https://x/"
+        );
+    }
+
+    #[test]
+    fn test_query_json() {
+        let json = query_to_json("https://x/cb?a=1&b%5B%5D=2&b[]=3&c[d]=a+b#frag").unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            json!({ "a": "1", "b": ["2", "3"], "c": { "d": "a b" } })
+        );
+        let value: Value = serde_json::from_str(&query_to_json("next=/a?b=1").unwrap()).unwrap();
+        assert_eq!(value, json!({ "next": "/a?b=1" }));
+
+        let query = json_to_query(
+            Source::Literal(r#"{"q":"a b","tags":["x","y"],"f":{"n":1}}"#),
+            "brackets",
+        )
+        .unwrap();
+        assert_eq!(query, "q=a%20b&tags%5B%5D=x&tags%5B%5D=y&f%5Bn%5D=1");
+        let query = json_to_query(Source::Literal(r#"{"tags":["x","y"]}"#), "repeat").unwrap();
+        assert_eq!(query, "tags=x&tags=y");
+        assert!(json_to_query(Source::Literal("[1]"), "brackets").is_err());
+    }
+
+    #[test]
+    fn test_normalize_and_resolve() {
+        assert_eq!(
+            normalize("HTTPS://Example.com:443/a/../b/%7e?z=1&a=2", true).unwrap(),
+            "https://example.com/b/~?a=2&z=1"
+        );
+        assert_eq!(
+            resolve("https://x.com/docs/guide/intro.html", "../img/a.png").unwrap(),
+            "https://x.com/docs/img/a.png"
+        );
+        assert!(resolve("docs/", "a").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let result = parse(
+            "https://bob@xn--bcher-kva.de/a/b%20c?tag=x&tag=y+z&empty#sec%201",
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "Scheme:   https\n\
+             Userinfo: bob\n\
+             Host:     xn--bcher-kva.de (domain)\n\
+             Unicode:  bücher.de\n\
+             Port:     443 (default)\n\
+             Path:     /a/b%20c\n\
+             \x20 1. a\n\
+             \x20 2. b c\n\
+             Query:    tag=x&tag=y+z&empty\n\
+             \x20 tag = x\n\
+             \x20 tag = y z\n\
+             \x20 empty = \n\
+             Fragment: sec 1"
+        );
+        assert!(parse("http://[::1]:8080", false)
+            .unwrap()
+            .contains("Host:     [::1] (ipv6)\nPort:     8080"));
+        assert!(parse("http://exa mple.com", false).is_err());
+
+        let result = parse("https://xn--pypal-4ve.com/login", false).unwrap();
+        assert!(result.contains("Unicode:  pаypal.com\nWarning:  Label 'pаypal' mixes scripts"));
+        assert!(result.contains("Warning:  Label 'pаypal' looks like the ASCII 'paypal'"));
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let result = parse("http://bücher.de:8080/?a=1&a=2", true).unwrap();
+        let value: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(value["host_ascii"], "xn--bcher-kva.de");
+        assert_eq!(value["host_unicode"], "bücher.de");
+        assert_eq!(value["host_warnings"], json!([]));
+        assert_eq!(value["port"], 8080);
+        assert_eq!(value["segments"], json!([]));
+        assert_eq!(value["params"][1], json!({ "key": "a", "value": "2" }));
+        assert_eq!(value["fragment"], Value::Null);
     }
 
     #[test]
     fn test_unicode() {
         let original = "Hello 🚀 World";
-        let encoded = encode(original).unwrap();
-        let decoded = decode(&encoded).unwrap();
+        let encoded = encode(original, Component::Value).unwrap();
+        let decoded = decode(&encoded, false, false).unwrap();
         assert_eq!(decoded, original);
     }
 }
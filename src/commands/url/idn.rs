@@ -0,0 +1,112 @@
+//! Internationalized domain names (`url idn`).
+//!
+//! Conversion follows UTS #46 (mapping, normalization and Punycode) through
+//! the idna crate. Labels that mix scripts or imitate an ASCII name are
+//! flagged using the UTS #39 script and confusable data.
+
+use anyhow::{anyhow, Result};
+use unicode_script::{Script, UnicodeScript};
+use unicode_security::{skeleton, MixedScript};
+
+/// The ASCII (Punycode) form of a domain, e.g. `xn--bcher-kva.de`
+pub fn to_ascii(domain: &str) -> Result<String> {
+    idna::domain_to_ascii(domain.trim())
+        .map_err(|err| anyhow!("Invalid domain name {:?}: {}", domain.trim(), err))
+}
+
+/// The Unicode form of a domain, e.g. `bücher.de`
+pub fn to_unicode(domain: &str) -> Result<String> {
+    let (unicode, result) = idna::domain_to_unicode(domain.trim());
+    result.map_err(|err| anyhow!("Invalid domain name {:?}: {}", domain.trim(), err))?;
+    Ok(unicode)
+}
+
+/// Reasons a domain (in Unicode form) may be a spoof of another name
+pub fn warnings(domain: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for label in domain.split('.').filter(|label| !label.is_ascii()) {
+        if !label.is_single_script() {
+            warnings.push(format!(
+                "Label '{}' mixes scripts: {}",
+                label,
+                scripts(label).join(", ")
+            ));
+        }
+        if let Some(lookalike) = ascii_lookalike(label) {
+            warnings.push(format!(
+                "Label '{}' looks like the ASCII '{}'",
+                label, lookalike
+            ));
+        }
+    }
+    warnings
+}
+
+/// Scripts used in `label`, in order of first use
+fn scripts(label: &str) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    for c in label.chars() {
+        let script = c.script();
+        if matches!(script, Script::Common | Script::Inherited | Script::Unknown) {
+            continue;
+        }
+        if !names.contains(&script.full_name()) {
+            names.push(script.full_name());
+        }
+    }
+    names
+}
+
+/// The ASCII text `label` imitates, if every non-ASCII character in it is
+/// confusable with a lowercase letter or digit
+fn ascii_lookalike(label: &str) -> Option<String> {
+    let prototype = |text: &str| skeleton(text).collect::<String>();
+    label
+        .chars()
+        .map(|c| {
+            if c.is_ascii() {
+                return Some(c);
+            }
+            let target = prototype(c.encode_utf8(&mut [0; 4]));
+            ('a'..='z')
+                .chain('0'..='9')
+                .find(|ascii| prototype(ascii.encode_utf8(&mut [0; 4])) == target)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(to_ascii("Bücher.DE").unwrap(), "xn--bcher-kva.de");
+        assert_eq!(to_ascii("例え.テスト").unwrap(), "xn--r8jz45g.xn--zckzah");
+        assert_eq!(to_unicode("xn--bcher-kva.de").unwrap(), "bücher.de");
+        // A label may not start with a combining mark
+        assert!(to_ascii("\u{301}a.com").is_err());
+        assert!(to_unicode("xn--a.de").is_err());
+    }
+
+    #[test]
+    fn test_warnings() {
+        // Cyrillic 'а' in an otherwise Latin name
+        let warnings = warnings("p\u{430}ypal.com");
+        assert_eq!(
+            warnings,
+            [
+                "Label 'pаypal' mixes scripts: Latin, Cyrillic",
+                "Label 'pаypal' looks like the ASCII 'paypal'",
+            ]
+        );
+        // All Cyrillic, but indistinguishable from "coca"
+        let coca = "\u{441}\u{43e}\u{441}\u{430}";
+        assert_eq!(
+            super::warnings(&format!("{}.com", coca)),
+            [format!("Label '{}' looks like the ASCII 'coca'", coca)]
+        );
+        assert!(super::warnings("bücher.de").is_empty());
+        assert!(super::warnings("例え.テスト").is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+//! RFC 3986 normalization (section 6) and reference resolution (section 5).
+
+use anyhow::{bail, Result};
+
+use super::parse::{default_port, HostKind, Url};
+
+/// Syntax- and scheme-based normalization: lowercase scheme and host, no
+/// default port, no dot segments, uppercase escapes, unreserved characters
+/// decoded and non-ASCII characters encoded; `sort_query` also orders the
+/// query parameters by key
+pub fn normalize(mut url: Url, sort_query: bool) -> Url {
+    url.scheme = url.scheme.map(|scheme| scheme.to_ascii_lowercase());
+
+    if let Some(authority) = &mut url.authority {
+        authority.userinfo = authority.userinfo.as_deref().map(normalize_escapes);
+        let host = normalize_escapes(&authority.host.to_lowercase());
+        let decoded = super::percent_decode(&host, false);
+        // Internationalized names become their ASCII (Punycode) form
+        if HostKind::of(&host) == HostKind::Domain && !decoded.is_ascii() {
+            authority.host = idna::domain_to_ascii(&decoded).unwrap_or(host);
+        } else {
+            authority.host = host;
+        }
+
+        let port = authority
+            .port
+            .as_deref()
+            .map(|port| match port.trim_start_matches('0') {
+                "" if !port.is_empty() => "0",
+                trimmed => trimmed,
+            });
+        let default = url.scheme.as_deref().and_then(default_port);
+        authority.port = match port {
+            None | Some("") => None,
+            Some(port) if default.is_some_and(|default| default.to_string() == port) => None,
+            Some(port) => Some(port.to_string()),
+        };
+        // `http://example.com` and `http://example.com/` are the same resource
+        if url.path.is_empty() {
+            url.path.push('/');
+        }
+    }
+
+    url.path = remove_dot_segments(&normalize_escapes(&url.path));
+    url.query = url.query.as_deref().map(|query| {
+        let query = normalize_escapes(query);
+        if !sort_query {
+            return query;
+        }
+        let mut pairs: Vec<&str> = query.split('&').filter(|pair| !pair.is_empty()).collect();
+        // Stable, so repeated keys keep their order
+        pairs.sort_by_key(|pair| pair.split_once('=').map_or(*pair, |(key, _)| key));
+        pairs.join("&")
+    });
+    url.fragment = url.fragment.as_deref().map(normalize_escapes);
+    url
+}
+
+/// Uppercase the hex digits of escapes, decode escaped unreserved characters
+/// and encode non-ASCII ones
+fn normalize_escapes(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%' && super::is_escape(bytes, i) {
+            let decoded = u8::from_str_radix(&text[i + 1..i + 3], 16).unwrap_or_default();
+            if decoded.is_ascii_alphanumeric() || b"-._~".contains(&decoded) {
+                out.push(decoded as char);
+            } else {
+                out.push_str(&format!("%{:02X}", decoded));
+            }
+            i += 3;
+        } else if b.is_ascii() {
+            out.push(b as char);
+            i += 1;
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Resolve `reference` against the absolute URL `base` (RFC 3986 section 5.2.2)
+pub fn resolve(base: &Url, reference: &Url) -> Result<Url> {
+    if base.scheme.is_none() {
+        bail!("The base URL must be absolute (start with a scheme such as https:)");
+    }
+
+    let mut target = Url {
+        fragment: reference.fragment.clone(),
+        ..Url::default()
+    };
+    if reference.scheme.is_some() {
+        target.scheme = reference.scheme.clone();
+        target.authority = reference.authority.clone();
+        target.path = remove_dot_segments(&reference.path);
+        target.query = reference.query.clone();
+        return Ok(target);
+    }
+
+    target.scheme = base.scheme.clone();
+    if reference.authority.is_some() {
+        target.authority = reference.authority.clone();
+        target.path = remove_dot_segments(&reference.path);
+        target.query = reference.query.clone();
+        return Ok(target);
+    }
+
+    target.authority = base.authority.clone();
+    if reference.path.is_empty() {
+        target.path = base.path.clone();
+        target.query = reference.query.clone().or_else(|| base.query.clone());
+    } else {
+        target.path = if reference.path.starts_with('/') {
+            remove_dot_segments(&reference.path)
+        } else {
+            remove_dot_segments(&merge(base, &reference.path))
+        };
+        target.query = reference.query.clone();
+    }
+    Ok(target)
+}
+
+/// Append a relative path to the base path's directory (section 5.2.3)
+fn merge(base: &Url, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{}", path);
+    }
+    match base.path.rfind('/') {
+        Some(slash) => format!("{}{}", &base.path[..=slash], path),
+        None => path.to_string(),
+    }
+}
+
+/// Interpret `.` and `..` segments (section 5.2.4)
+pub fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output = String::with_capacity(path.len());
+
+    while !input.is_empty() {
+        if let Some(rest) = input
+            .strip_prefix("../")
+            .or_else(|| input.strip_prefix("./"))
+        {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.truncate(output.rfind('/').unwrap_or(0));
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            // Move the first segment, with its leading slash, to the output
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..].find('/').map_or(input.len(), |i| i + start);
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse::parse;
+    use super::*;
+
+    fn norm(input: &str, sort_query: bool) -> String {
+        normalize(parse(input).unwrap(), sort_query).to_string()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            norm(
+                "HTTP://User@Example.COM:80/a/./b/../c/%7euser/%c3%a9?q=%41%2f#F%6f",
+                false
+            ),
+            "http://User@example.com/a/c/~user/%C3%A9?q=A%2F#Fo"
+        );
+        assert_eq!(
+            norm("https://example.com:8443", false),
+            "https://example.com:8443/"
+        );
+        assert_eq!(
+            norm("https://Bücher.de/straße", false),
+            "https://xn--bcher-kva.de/stra%C3%9Fe"
+        );
+        assert_eq!(norm("http://x/?b=2&a=1&b=1", true), "http://x/?a=1&b=2&b=1");
+        assert_eq!(
+            norm("mailto:Someone@Example.com", false),
+            "mailto:Someone@Example.com"
+        );
+    }
+
+    #[test]
+    fn test_remove_dot_segments() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("mid/content=5/../6"), "mid/6");
+        assert_eq!(remove_dot_segments("/../a"), "/a");
+        assert_eq!(remove_dot_segments("/a/.."), "/");
+    }
+
+    #[test]
+    fn test_resolve_rfc_examples() {
+        let base = parse("http://a/b/c/d;p?q").unwrap();
+        let cases = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            (";x", "http://a/b/c/;x"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+        ];
+        for (reference, expected) in cases {
+            let resolved = resolve(&base, &parse(reference).unwrap()).unwrap();
+            assert_eq!(resolved.to_string(), expected, "{}", reference);
+        }
+        assert!(resolve(&parse("/relative").unwrap(), &parse("g").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! RFC 3986 URI references split into their components.
+//!
+//! Components are kept exactly as written (still percent-encoded) so a
+//! reference can be put back together unchanged; decoding is left to the
+//! caller. Non-ASCII characters are accepted as in IRIs (RFC 3987).
+
+use anyhow::{bail, Result};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// A URI or relative reference
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Url {
+    pub scheme: Option<String>,
+    pub authority: Option<Authority>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+/// `userinfo@host:port`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Authority {
+    pub userinfo: Option<String>,
+    /// Registered name, IPv4 address, or IP literal including its brackets
+    pub host: String,
+    /// Digits only; may be empty, as in `http://example.com:/`
+    pub port: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HostKind {
+    Domain,
+    Ipv4,
+    Ipv6,
+    IpFuture,
+}
+
+impl HostKind {
+    pub fn of(host: &str) -> HostKind {
+        match host.strip_prefix('[') {
+            Some(literal) if literal.starts_with(['v', 'V']) => HostKind::IpFuture,
+            Some(_) => HostKind::Ipv6,
+            None if host.parse::<Ipv4Addr>().is_ok() => HostKind::Ipv4,
+            None => HostKind::Domain,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HostKind::Domain => "domain",
+            HostKind::Ipv4 => "ipv4",
+            HostKind::Ipv6 => "ipv6",
+            HostKind::IpFuture => "ipvfuture",
+        }
+    }
+}
+
+/// The port a scheme uses when none is given
+pub fn default_port(scheme: &str) -> Option<u16> {
+    match scheme.to_ascii_lowercase().as_str() {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        "ssh" | "sftp" => Some(22),
+        "ldap" => Some(389),
+        "ldaps" => Some(636),
+        _ => None,
+    }
+}
+
+const SUB_DELIMS: &str = "!$&'()*+,;=";
+
+/// Split `input` into components, rejecting characters that RFC 3986 does
+/// not allow where they appear
+pub fn parse(input: &str) -> Result<Url> {
+    let mut url = Url::default();
+    let mut rest = input;
+
+    if let Some(hash) = rest.find('#') {
+        let fragment = &rest[hash + 1..];
+        check(input, fragment, "fragment", ":@/?")?;
+        url.fragment = Some(fragment.to_string());
+        rest = &rest[..hash];
+    }
+    if let Some(question) = rest.find('?') {
+        let query = &rest[question + 1..];
+        check(input, query, "query", ":@/?")?;
+        url.query = Some(query.to_string());
+        rest = &rest[..question];
+    }
+
+    // A colon before the first slash ends the scheme; relative references
+    // cannot have one in their first segment
+    let first_segment = &rest[..rest.find('/').unwrap_or(rest.len())];
+    if let Some(colon) = first_segment.find(':') {
+        let scheme = &rest[..colon];
+        if !is_scheme(scheme) {
+            bail!(
+                "Invalid scheme {:?}: it must start with a letter and contain only letters, digits, '+', '-' or '.'",
+                scheme
+            );
+        }
+        url.scheme = Some(scheme.to_string());
+        rest = &rest[colon + 1..];
+    }
+
+    if let Some(after) = rest.strip_prefix("//") {
+        let end = after.find('/').unwrap_or(after.len());
+        url.authority = Some(parse_authority(input, &after[..end])?);
+        rest = &after[end..];
+    }
+    check(input, rest, "path", ":@/")?;
+    url.path = rest.to_string();
+
+    Ok(url)
+}
+
+fn is_scheme(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+fn parse_authority(input: &str, text: &str) -> Result<Authority> {
+    let (userinfo, host_port) = match text.rfind('@') {
+        Some(at) => (Some(&text[..at]), &text[at + 1..]),
+        None => (None, text),
+    };
+    if let Some(userinfo) = userinfo {
+        check(input, userinfo, "userinfo", ":")?;
+    }
+
+    let (host, port) = if host_port.starts_with('[') {
+        let Some(close) = host_port.find(']') else {
+            bail!("Unclosed '[' in host {:?}", host_port);
+        };
+        let port = match &host_port[close + 1..] {
+            "" => None,
+            after => match after.strip_prefix(':') {
+                Some(port) => Some(port),
+                None => bail!("Unexpected {:?} after IP literal", after),
+            },
+        };
+        let literal = &host_port[1..close];
+        if !literal.starts_with(['v', 'V']) {
+            // A zone identifier such as %25eth0 may follow the address (RFC 6874)
+            let address = literal
+                .split_once("%25")
+                .map_or(literal, |(address, _)| address);
+            if address.parse::<Ipv6Addr>().is_err() {
+                bail!("Invalid IPv6 address: [{}]", literal);
+            }
+        }
+        (&host_port[..=close], port)
+    } else {
+        let (host, port) = match host_port.rfind(':') {
+            Some(colon) => (&host_port[..colon], Some(&host_port[colon + 1..])),
+            None => (host_port, None),
+        };
+        check(input, host, "host", "")?;
+        (host, port)
+    };
+
+    if let Some(port) = port {
+        if !port.is_empty() && port.parse::<u16>().is_err() || port.starts_with('+') {
+            bail!("Invalid port {:?}: it must be a number up to 65535", port);
+        }
+    }
+
+    Ok(Authority {
+        userinfo: userinfo.map(str::to_string),
+        host: host.to_string(),
+        port: port.map(str::to_string),
+    })
+}
+
+/// Allow unreserved characters, sub-delimiters, `extra`, non-ASCII text and
+/// well-formed percent-escapes in `part`, a slice of `input`
+fn check(input: &str, part: &str, what: &str, extra: &str) -> Result<()> {
+    let start = part.as_ptr() as usize - input.as_ptr() as usize;
+    let bytes = part.as_bytes();
+
+    for (i, c) in part.char_indices() {
+        let allowed = c.is_ascii_alphanumeric()
+            || matches!(c, '-' | '.' | '_' | '~')
+            || SUB_DELIMS.contains(c)
+            || extra.contains(c)
+            || !c.is_ascii() && !c.is_control() && !c.is_whitespace();
+        if allowed {
+            continue;
+        }
+        if c == '%'
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_hexdigit)
+            && bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit)
+        {
+            continue;
+        }
+        if c == '%' {
+            bail!("Malformed escape in {} at offset {}", what, start + i);
+        }
+        bail!(
+            "Invalid character {:?} in {} at offset {}",
+            c,
+            what,
+            start + i
+        );
+    }
+    Ok(())
+}
+
+impl Url {
+    /// Path segments after the leading slash; empty for an empty path or `/`
+    pub fn segments(&self) -> Vec<&str> {
+        let path = self.path.strip_prefix('/').unwrap_or(&self.path);
+        if path.is_empty() {
+            Vec::new()
+        } else {
+            path.split('/').collect()
+        }
+    }
+}
+
+/// Recompose the reference (RFC 3986 section 5.3)
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(scheme) = &self.scheme {
+            write!(f, "{}:", scheme)?;
+        }
+        if let Some(authority) = &self.authority {
+            f.write_str("//")?;
+            if let Some(userinfo) = &authority.userinfo {
+                write!(f, "{}@", userinfo)?;
+            }
+            f.write_str(&authority.host)?;
+            if let Some(port) = &authority.port {
+                write!(f, ":{}", port)?;
+            }
+        }
+        f.write_str(&self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_components() {
+        let url = parse("https://user:pw@Example.com:8443/a/b%20c?q=1&q=2#top").unwrap();
+        assert_eq!(url.scheme.as_deref(), Some("https"));
+        let authority = url.authority.as_ref().unwrap();
+        assert_eq!(authority.userinfo.as_deref(), Some("user:pw"));
+        assert_eq!(authority.host, "Example.com");
+        assert_eq!(authority.port.as_deref(), Some("8443"));
+        assert_eq!(url.path, "/a/b%20c");
+        assert_eq!(url.segments(), ["a", "b%20c"]);
+        assert_eq!(url.query.as_deref(), Some("q=1&q=2"));
+        assert_eq!(url.fragment.as_deref(), Some("top"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for input in [
+            "http://[2001:db8::1]:80/",
+            "mailto:someone@example.com",
+            "urn:isbn:0451450523",
+            "file:///etc/hosts",
+            "//cdn.example.com/x.js",
+            "../up?x#",
+            "https://bücher.de/straße",
+            "",
+        ] {
+            assert_eq!(parse(input).unwrap().to_string(), input);
+        }
+        let url = parse("http://[fe80::1%25eth0]/").unwrap();
+        assert_eq!(HostKind::of(&url.authority.unwrap().host), HostKind::Ipv6);
+        assert_eq!(HostKind::of("192.168.0.1"), HostKind::Ipv4);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse("http://example.com/a b").unwrap_err().to_string();
+        assert_eq!(err, "Invalid character ' ' in path at offset 20");
+        let err = parse("http://example.com/?q=%G1").unwrap_err().to_string();
+        assert_eq!(err, "Malformed escape in query at offset 22");
+        assert!(parse("1http://example.com").is_err());
+        assert!(parse("http://example.com:80a/").is_err());
+        assert!(parse("http://[::1/").is_err());
+        assert!(parse("http://[not-ip]/").is_err());
+    }
+}
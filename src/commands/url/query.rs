@@ -0,0 +1,296 @@
+//! Query strings as nested JSON (`url query-to-json` and `url json-to-query`).
+//!
+//! Keys follow the PHP/Rails bracket conventions: `a[]` appends to an array,
+//! `a[b]` sets a field and `a[0]` an array slot. A plain key given more than
+//! once collects its values into an array. Values stay strings.
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+/// How `json-to-query` writes array elements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrayStyle {
+    /// `a[]=1&a[]=2`
+    Brackets,
+    /// `a[0]=1&a[1]=2`
+    Index,
+    /// `a=1&a=2`
+    Repeat,
+}
+
+impl ArrayStyle {
+    pub fn parse(name: &str) -> Result<ArrayStyle> {
+        match name.to_lowercase().as_str() {
+            "brackets" | "php" | "rails" => Ok(ArrayStyle::Brackets),
+            "index" | "indices" => Ok(ArrayStyle::Index),
+            "repeat" => Ok(ArrayStyle::Repeat),
+            _ => bail!(
+                "Unknown array style: {}. Use brackets, index, or repeat.",
+                name
+            ),
+        }
+    }
+}
+
+/// Nest decoded `key=value` pairs into a JSON object
+pub fn to_json(pairs: &[(String, String)]) -> Result<Value> {
+    let mut root = Value::Object(Map::new());
+    for (key, value) in pairs {
+        let (name, path) = split_key(key);
+        let Value::Object(map) = &mut root else {
+            unreachable!("the root is always an object");
+        };
+        let slot = map.entry(name).or_insert(Value::Null);
+        place(slot, &path, value.clone(), key)?;
+    }
+    Ok(root)
+}
+
+/// `a[b][]` -> (`a`, [`b`, ``]); keys with unbalanced brackets are kept whole
+fn split_key(key: &str) -> (String, Vec<String>) {
+    let Some(open) = key.find('[').filter(|open| *open > 0) else {
+        return (key.to_string(), Vec::new());
+    };
+    let mut path = Vec::new();
+    let mut rest = &key[open..];
+    while let Some(inner) = rest.strip_prefix('[') {
+        let Some(close) = inner.find(']') else {
+            return (key.to_string(), Vec::new());
+        };
+        path.push(inner[..close].to_string());
+        rest = &inner[close + 1..];
+    }
+    if !rest.is_empty() {
+        return (key.to_string(), Vec::new());
+    }
+    (key[..open].to_string(), path)
+}
+
+fn place(slot: &mut Value, path: &[String], value: String, key: &str) -> Result<()> {
+    let Some((step, rest)) = path.split_first() else {
+        // A plain key seen again becomes an array of its values
+        match slot {
+            Value::Null => *slot = Value::String(value),
+            Value::String(first) => {
+                *slot = Value::Array(vec![Value::String(first.clone()), Value::String(value)])
+            }
+            Value::Array(items) => items.push(Value::String(value)),
+            _ => bail!(
+                "Parameter {} conflicts with an earlier value of the same name",
+                key
+            ),
+        }
+        return Ok(());
+    };
+
+    if step.is_empty() || step.bytes().all(|b| b.is_ascii_digit()) && !slot.is_object() {
+        if slot.is_null() {
+            *slot = Value::Array(Vec::new());
+        }
+        let Value::Array(items) = slot else {
+            bail!(
+                "Parameter {} conflicts with an earlier value of the same name",
+                key
+            );
+        };
+        let index = match step.parse::<usize>() {
+            Ok(index) if index < items.len() => index,
+            // `a[]`, or an index past the end: append (sparse indices are compacted)
+            _ => {
+                // `a[][b]=1&a[][c]=2` fills one element until a field repeats
+                let reuse = step.is_empty()
+                    && matches!(
+                        (items.last(), rest.first()),
+                        (Some(Value::Object(last)), Some(field)) if !last.contains_key(field)
+                    );
+                if !reuse {
+                    items.push(Value::Null);
+                }
+                items.len() - 1
+            }
+        };
+        return place(&mut items[index], rest, value, key);
+    }
+
+    if slot.is_null() {
+        *slot = Value::Object(Map::new());
+    }
+    let Value::Object(map) = slot else {
+        bail!(
+            "Parameter {} conflicts with an earlier value of the same name",
+            key
+        );
+    };
+    place(
+        map.entry(step.clone()).or_insert(Value::Null),
+        rest,
+        value,
+        key,
+    )
+}
+
+/// Flatten a JSON object into `key=value` pairs, the reverse of [`to_json`]
+///
+/// Empty arrays and objects, and keys containing brackets, have no query
+/// form that reads back the same, so they are rejected.
+pub fn from_json(value: &Value, style: ArrayStyle) -> Result<Vec<(String, String)>> {
+    let Value::Object(map) = value else {
+        bail!("Expected a JSON object at the top level to turn into a query string");
+    };
+    let mut pairs = Vec::new();
+    for (key, value) in map {
+        flatten(checked(key, "")?, value, style, &mut pairs)?;
+    }
+    Ok(pairs)
+}
+
+fn checked(field: &str, parent: &str) -> Result<String> {
+    let key = if parent.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}[{}]", parent, field)
+    };
+    if field.contains(['[', ']']) {
+        bail!(
+            "Key {} contains brackets, which would read back as nested data",
+            key
+        );
+    }
+    Ok(key)
+}
+
+fn flatten(
+    key: String,
+    value: &Value,
+    style: ArrayStyle,
+    pairs: &mut Vec<(String, String)>,
+) -> Result<()> {
+    match value {
+        Value::Object(map) if map.is_empty() => {
+            bail!("Empty object at {} has no query string form", key)
+        }
+        Value::Array(items) if items.is_empty() => {
+            bail!("Empty array at {} has no query string form", key)
+        }
+        Value::Object(map) => {
+            for (field, value) in map {
+                flatten(checked(field, &key)?, value, style, pairs)?;
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                // Nested containers need an index to stay apart
+                let item_key = match style {
+                    _ if item.is_object() || item.is_array() => format!("{}[{}]", key, i),
+                    ArrayStyle::Index => format!("{}[{}]", key, i),
+                    ArrayStyle::Brackets => format!("{}[]", key),
+                    ArrayStyle::Repeat => key.clone(),
+                };
+                flatten(item_key, item, style, pairs)?;
+            }
+        }
+        Value::String(text) => pairs.push((key, text.clone())),
+        Value::Null => pairs.push((key, String::new())),
+        other => pairs.push((key, other.to_string())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn nest(pairs: &[(&str, &str)]) -> Result<Value> {
+        let pairs: Vec<(String, String)> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        to_json(&pairs)
+    }
+
+    #[test]
+    fn test_to_json() {
+        let value = nest(&[
+            ("a", "1"),
+            ("b[]", "2"),
+            ("b[]", "3"),
+            ("c[d]", "4"),
+            ("c[e][]", "5"),
+            ("tag", "x"),
+            ("tag", "y"),
+            ("items[0][id]", "7"),
+            ("items[1][id]", "8"),
+            ("rows[][x]", "1"),
+            ("rows[][y]", "2"),
+            ("rows[][x]", "3"),
+            ("odd[", "z"),
+        ])
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "a": "1",
+                "b": ["2", "3"],
+                "c": { "d": "4", "e": ["5"] },
+                "tag": ["x", "y"],
+                "items": [{ "id": "7" }, { "id": "8" }],
+                "rows": [{ "x": "1", "y": "2" }, { "x": "3" }],
+                "odd[": "z",
+            })
+        );
+        assert!(nest(&[("a", "1"), ("a[b]", "2")]).is_err());
+        assert!(nest(&[("a[b]", "1"), ("a", "2")]).is_err());
+    }
+
+    #[test]
+    fn test_from_json_round_trips() {
+        let value = json!({
+            "a": 1,
+            "b": ["2", true],
+            "c": { "d": null, "e": [{ "f": "g" }] },
+        });
+        for style in [ArrayStyle::Brackets, ArrayStyle::Index, ArrayStyle::Repeat] {
+            let pairs = from_json(&value, style).unwrap();
+            let back = to_json(&pairs).unwrap();
+            assert_eq!(
+                back,
+                json!({ "a": "1", "b": ["2", "true"], "c": { "d": "", "e": [{ "f": "g" }] } }),
+                "{:?}",
+                style
+            );
+        }
+        let keys: Vec<String> = from_json(&value, ArrayStyle::Brackets)
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, ["a", "b[]", "b[]", "c[d]", "c[e][0][f]"]);
+        assert!(from_json(&json!([1]), ArrayStyle::Brackets).is_err());
+    }
+
+    #[test]
+    fn test_from_json_rejects_lossy_values() {
+        let err = |value: Value| {
+            from_json(&value, ArrayStyle::Brackets)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err(json!({ "a": 1, "b": [] })),
+            "Empty array at b has no query string form"
+        );
+        assert_eq!(
+            err(json!({ "a": { "b": [{ "c": {} }] } })),
+            "Empty object at a[b][0][c] has no query string form"
+        );
+        assert_eq!(
+            err(json!({ "a[b]": 1 })),
+            "Key a[b] contains brackets, which would read back as nested data"
+        );
+        assert_eq!(
+            err(json!({ "a": { "b]": 1 } })),
+            "Key a[b]] contains brackets, which would read back as nested data"
+        );
+    }
+}
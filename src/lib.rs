@@ -0,0 +1,3 @@
+//! DevKit command implementations, shared by the CLI and the benchmarks.
+
+pub mod commands;
@@ -1,7 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use colored::*;
-
-mod commands;
+use devkit::commands;
+use devkit::commands::input::Source;
+use devkit::commands::json::pointer::ValueType;
+use devkit::commands::Output;
+use std::path::Path;
 
 #[derive(Parser)]
 #[command(name = "devkit")]
@@ -51,6 +54,25 @@ enum Commands {
         action: JsonAction,
     },
 
+    /// 🔄 Convert between JSON, YAML and TOML
+    Convert {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Input format: json, yaml, toml (detected when omitted)
+        #[arg(short, long)]
+        from: Option<String>,
+        /// Output format: json, yaml, toml
+        #[arg(short, long, default_value = "json")]
+        to: String,
+    },
+
+    /// 📊 CSV conversion
+    #[command(arg_required_else_help = true)]
+    Csv {
+        #[command(subcommand)]
+        action: CsvAction,
+    },
+
     /// ⏰ Unix timestamp conversions
     #[command(arg_required_else_help = true)]
     Time {
@@ -58,6 +80,24 @@ enum Commands {
         action: TimeAction,
     },
 
+    /// 🛡️ Escape a string for JSON, shell, regex, HTML, XML, CSV, C or URLs
+    Escape {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Target context: json, shell, regex, html, xml, csv, c, url
+        #[arg(long = "for", value_name = "TARGET")]
+        target: String,
+    },
+
+    /// 🛡️ Reverse `escape` for the given context
+    Unescape {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Source context: json, shell, regex, html, xml, csv, c, url
+        #[arg(long = "for", value_name = "TARGET")]
+        target: String,
+    },
+
     /// 🔗 URL encode/decode strings
     #[command(arg_required_else_help = true)]
     Url {
@@ -66,6 +106,69 @@ enum Commands {
     },
 }
 
+/// Input shared by the subcommands that read a document
+#[derive(Args)]
+struct InputArgs {
+    /// Literal text, @path to read a file, or @- for stdin
+    input: String,
+    /// Read INPUT as a file path, taken as-is (even if it starts with @)
+    #[arg(long)]
+    file: bool,
+}
+
+impl InputArgs {
+    fn source(&self) -> Source<'_> {
+        if self.file {
+            Source::File(Path::new(&self.input))
+        } else {
+            commands::input::source(&self.input)
+        }
+    }
+}
+
+/// Zone for time subcommands that read dates
+#[derive(Args)]
+struct ZoneArgs {
+    /// Zone for input without an offset, e.g. America/New_York (default UTC)
+    #[arg(long)]
+    tz: Option<String>,
+}
+
+impl ZoneArgs {
+    fn tz(&self) -> Option<&str> {
+        self.tz.as_deref()
+    }
+}
+
+/// Output format shared by the time subcommands
+#[derive(Args)]
+struct FormatArgs {
+    /// Print times as rfc3339, rfc2822, iso-week, http, unix, unix-ms,
+    /// or a strftime pattern like '%Y%m%dT%H%M%S'
+    #[arg(long)]
+    format: Option<String>,
+}
+
+/// How the time subcommands that print one moment show it
+#[derive(Args)]
+struct DisplayArgs {
+    /// Also show the time in these zones (comma-separated IANA names)
+    #[arg(long, value_name = "ZONES", conflicts_with = "format")]
+    show_tz: Option<String>,
+    #[command(flatten)]
+    output: FormatArgs,
+}
+
+impl DisplayArgs {
+    fn show_tz(&self) -> Option<&str> {
+        self.show_tz.as_deref()
+    }
+
+    fn format(&self) -> Option<&str> {
+        self.output.format.as_deref()
+    }
+}
+
 #[derive(Subcommand)]
 enum Base64Action {
     /// Encode a string to Base64
@@ -96,18 +199,27 @@ enum Base64Action {
 enum HashAction {
     /// Generate MD5 hash
     Md5 {
-        /// Input string to hash
-        input: String,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Hash the RFC 8785 canonical form of JSON input
+        #[arg(long)]
+        canonical: bool,
     },
     /// Generate SHA256 hash
     Sha256 {
-        /// Input string to hash
-        input: String,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Hash the RFC 8785 canonical form of JSON input
+        #[arg(long)]
+        canonical: bool,
     },
     /// Generate SHA512 hash
     Sha512 {
-        /// Input string to hash
-        input: String,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Hash the RFC 8785 canonical form of JSON input
+        #[arg(long)]
+        canonical: bool,
     },
     /// Hash a file
     File {
@@ -116,6 +228,9 @@ enum HashAction {
         /// Algorithm: md5, sha256, sha512
         #[arg(short, long, default_value = "sha256")]
         algorithm: String,
+        /// Hash the RFC 8785 canonical form of the file's JSON
+        #[arg(long)]
+        canonical: bool,
     },
 }
 
@@ -123,37 +238,225 @@ enum HashAction {
 enum JsonAction {
     /// Format/prettify JSON
     Format {
-        /// JSON string or file path
-        input: String,
+        #[command(flatten)]
+        input: InputArgs,
         /// Indentation spaces
         #[arg(short, long, default_value = "2")]
         indent: usize,
     },
     /// Minify JSON (remove whitespace)
     Minify {
-        /// JSON string or file path
-        input: String,
+        #[command(flatten)]
+        input: InputArgs,
     },
     /// Validate JSON syntax
     Validate {
-        /// JSON string or file path
-        input: String,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Show size and shape statistics
+    Stats {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Number of largest arrays and heaviest subtrees to list
+        #[arg(short = 'n', long, default_value = "10")]
+        top: usize,
+    },
+    /// Flatten an array of objects into CSV
+    ToCsv {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Separator between nested key names in column headers
+        #[arg(short, long, default_value = ".")]
+        separator: String,
+        /// Array handling: index, join, json
+        #[arg(short, long, default_value = "index")]
+        arrays: String,
+    },
+    /// Serialize in RFC 8785 canonical form (sorted keys, no whitespace)
+    Canonicalize {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Print the value at a JSON pointer (exits 3 if missing)
+    Get {
+        /// RFC 6901 pointer, e.g. /spec/replicas
+        pointer: String,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Print strings without JSON quotes
+        #[arg(short, long)]
+        raw: bool,
+    },
+    /// Set the value at a JSON pointer (exits 3 if the parent is missing)
+    Set {
+        /// RFC 6901 pointer, e.g. /spec/replicas (`-` appends to an array)
+        pointer: String,
+        /// New value: parsed as JSON if possible, otherwise a string
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Always treat VALUE as a string
+        #[arg(long, conflicts_with = "json")]
+        string: bool,
+        /// Require VALUE to be valid JSON
+        #[arg(long)]
+        json: bool,
+        /// Create missing parent objects
+        #[arg(short, long)]
+        parents: bool,
+        /// Write the result back to the input file
+        #[arg(short, long)]
+        in_place: bool,
+    },
+    /// Delete the value at a JSON pointer (exits 3 if missing)
+    Delete {
+        /// RFC 6901 pointer, e.g. /metadata/annotations
+        pointer: String,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Write the result back to the input file
+        #[arg(short, long)]
+        in_place: bool,
+    },
+    /// Generate Rust, TypeScript or Go types from a sample
+    Codegen {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Target language: rust, ts, go
+        #[arg(short, long, default_value = "rust")]
+        lang: String,
+        /// Name of the top-level type
+        #[arg(short, long, default_value = "Root")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CsvAction {
+    /// Convert CSV with a header row to a JSON array
+    ToJson {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Field delimiter
+        #[arg(short, long, default_value = ",")]
+        delimiter: char,
+        /// Keep every value as a string instead of inferring types
+        #[arg(long)]
+        no_infer: bool,
     },
 }
 
 #[derive(Subcommand)]
 enum TimeAction {
     /// Show current time as Unix timestamp
-    Now,
+    Now {
+        #[command(flatten)]
+        display: DisplayArgs,
+    },
     /// Convert Unix timestamp to human-readable
     FromUnix {
-        /// Unix timestamp (seconds)
+        /// Unix timestamp (unit detected from its magnitude unless --unit is given)
+        #[arg(allow_negative_numbers = true)]
         timestamp: i64,
+        /// Timestamp unit: s, ms, us, or ns
+        #[arg(long)]
+        unit: Option<String>,
+        #[command(flatten)]
+        display: DisplayArgs,
+    },
+    /// Show when a snowflake, ULID, KSUID, MongoDB ObjectId or UUID v1/v6/v7 was created
+    FromId {
+        /// The ID; its type is detected from its shape
+        id: String,
+        /// ID type when detection guesses wrong: twitter, discord, ulid, ksuid, objectid, uuid
+        #[arg(long)]
+        kind: Option<String>,
+        #[command(flatten)]
+        display: DisplayArgs,
     },
     /// Convert human-readable date to Unix timestamp
     ToUnix {
-        /// Date string (e.g., "2024-12-28 12:00:00")
+        /// Date string (e.g., "2024-12-28 12:00:00", "Sat, 28 Dec 2024 12:00:00 GMT",
+        /// "2 hours ago", "next monday 09:00")
+        date: String,
+        #[command(flatten)]
+        zone: ZoneArgs,
+        #[command(flatten)]
+        display: DisplayArgs,
+    },
+    /// Add a duration to a date (e.g. "now" "90d", or "2024-12-28" "-1h30m")
+    Add {
+        /// Date in any form accepted by to-unix
         date: String,
+        /// Duration such as 90d, 1h30m, "2 hours", 3725 or PT1H2M5S
+        #[arg(allow_hyphen_values = true)]
+        duration: String,
+        #[command(flatten)]
+        zone: ZoneArgs,
+        #[command(flatten)]
+        display: DisplayArgs,
+    },
+    /// Time between two dates
+    Diff {
+        /// Start date in any form accepted by to-unix
+        from: String,
+        /// End date in any form accepted by to-unix
+        to: String,
+        #[command(flatten)]
+        zone: ZoneArgs,
+    },
+    /// Explain a cron expression and list its next run times
+    Cron {
+        /// Expression: 5 fields, 6-7 Quartz-style fields, or a macro like @daily
+        expression: String,
+        /// Number of upcoming runs to list
+        #[arg(short, long, default_value = "5")]
+        next: usize,
+        /// Zone the schedule runs in, e.g. America/New_York (default UTC)
+        #[arg(long)]
+        tz: Option<String>,
+        #[command(flatten)]
+        output: FormatArgs,
+    },
+    /// Annotate epoch and ISO timestamps in a log with human-readable times
+    ///
+    /// Without --format the readable time looks like '%Y-%m-%d %H:%M:%S %Z'.
+    Annotate {
+        /// Log file to read, or - for stdin
+        #[arg(default_value = "-")]
+        path: String,
+        /// Zone to show times in (default UTC); ISO times without an offset are read as UTC
+        #[arg(long)]
+        tz: Option<String>,
+        /// Replace timestamps instead of appending the readable time in brackets
+        #[arg(short, long)]
+        replace: bool,
+        #[command(flatten)]
+        output: FormatArgs,
+    },
+    /// Show the ISO week, day of year, quarter and leap-year flag of a date
+    Info {
+        /// Date in any form accepted by to-unix, e.g. 2024-12-28 or "next friday"
+        date: String,
+        /// Zone for input without an offset and for the calendar day (default UTC)
+        #[arg(long)]
+        tz: Option<String>,
+    },
+    /// Print a month calendar with ISO week numbers
+    Cal {
+        /// Month as YYYY-MM, or any date in it (default: the current month)
+        month: Option<String>,
+        /// Zone used to find the current month (default UTC)
+        #[arg(long)]
+        tz: Option<String>,
+    },
+    /// Show a duration as "1h 2m 5s", ISO 8601 and seconds
+    Duration {
+        /// Duration such as 3725s, 1h30m, "2 hours" or PT1H2M5S
+        #[arg(allow_hyphen_values = true)]
+        duration: String,
     },
 }
 
@@ -163,11 +466,111 @@ enum UrlAction {
     Encode {
         /// String to encode
         input: String,
+        /// What the text is for, which decides the characters left as-is:
+        /// value (only unreserved), path, segment, query, fragment, userinfo, form (space as +),
+        /// or full (a whole URL; delimiters and existing escapes are kept)
+        #[arg(long, default_value = "value")]
+        component: String,
     },
     /// URL decode a string
     Decode {
         /// URL-encoded string to decode
         input: String,
+        /// Treat + as a space (application/x-www-form-urlencoded data)
+        #[arg(long)]
+        form: bool,
+        /// Fail on malformed escapes and invalid UTF-8, listing each with its offset
+        #[arg(long)]
+        strict: bool,
+        /// Write the decoded bytes as they are, for payloads that are not UTF-8 text
+        #[arg(long)]
+        bytes: bool,
+    },
+    /// Build a URL from a base, path segments and query parameters, encoding each
+    Build {
+        /// Base URL, e.g. https://example.com/api
+        #[arg(long)]
+        base: String,
+        /// Path segment to append (repeatable; '/' inside it is encoded)
+        #[arg(long = "path", value_name = "SEGMENT")]
+        segments: Vec<String>,
+        /// Query parameter to append (repeatable; repeated keys are kept)
+        #[arg(long = "query", value_name = "KEY=VALUE")]
+        params: Vec<String>,
+        /// Fragment to set
+        #[arg(long)]
+        fragment: Option<String>,
+    },
+    /// Set a query parameter, replacing any existing values
+    SetQuery {
+        /// URL to edit
+        url: String,
+        /// Parameter name, matched after decoding
+        key: String,
+        /// New value (encoded for you)
+        value: String,
+    },
+    /// Remove every occurrence of a query parameter
+    RemoveQuery {
+        /// URL to edit
+        url: String,
+        /// Parameter name, matched after decoding
+        key: String,
+    },
+    /// Decode a query string (or a URL's query) into nested JSON, e.g. a[]=1&b[c]=2
+    QueryToJson {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Encode a JSON object as a query string, nesting with brackets
+    JsonToQuery {
+        #[command(flatten)]
+        input: InputArgs,
+        /// How to write arrays: brackets (a[]=1), index (a[0]=1), or repeat (a=1&a=2)
+        #[arg(long, default_value = "brackets")]
+        arrays: String,
+    },
+    /// Normalize a URL (RFC 3986) for deduplication and cache keys
+    Normalize {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Also sort query parameters by key
+        #[arg(long)]
+        sort_query: bool,
+    },
+    /// Resolve a relative reference against a base URL
+    Resolve {
+        /// Absolute base URL, e.g. https://example.com/docs/index.html
+        base: String,
+        /// Reference to resolve, e.g. ../img/logo.png or ?page=2
+        reference: String,
+    },
+    /// Convert internationalized domain names (UTS #46 / Punycode)
+    Idn {
+        #[command(subcommand)]
+        action: IdnAction,
+    },
+    /// Break a URL into scheme, userinfo, host, port, path, query and fragment
+    Parse {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Print the components as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum IdnAction {
+    /// Convert to the ASCII form, e.g. bücher.de -> xn--bcher-kva.de
+    ToAscii {
+        /// Domain name
+        domain: String,
+    },
+    /// Convert to the Unicode form, warning about mixed scripts and lookalikes
+    ToUnicode {
+        /// Domain name
+        domain: String,
     },
 }
 
@@ -182,44 +585,315 @@ fn main() {
             Base64Action::DecodeFile { input, output } => {
                 commands::base64::decode_file(&input, &output)
             }
-        },
+        }
+        .map(Output::from),
 
         Commands::Uuid {
             count,
             version,
             uppercase,
-        } => commands::uuid::generate(count, version, uppercase),
+        } => commands::uuid::generate(count, version, uppercase).map(Output::from),
 
         Commands::Hash { action } => match action {
-            HashAction::Md5 { input } => commands::hash::md5(&input),
-            HashAction::Sha256 { input } => commands::hash::sha256(&input),
-            HashAction::Sha512 { input } => commands::hash::sha512(&input),
-            HashAction::File { path, algorithm } => commands::hash::hash_file(&path, &algorithm),
-        },
+            HashAction::Md5 { input, canonical } => {
+                commands::hash::hash(input.source(), "md5", canonical)
+            }
+            HashAction::Sha256 { input, canonical } => {
+                commands::hash::hash(input.source(), "sha256", canonical)
+            }
+            HashAction::Sha512 { input, canonical } => {
+                commands::hash::hash(input.source(), "sha512", canonical)
+            }
+            HashAction::File {
+                path,
+                algorithm,
+                canonical,
+            } => commands::hash::hash_file(&path, &algorithm, canonical),
+        }
+        .map(Output::from),
 
         Commands::Json { action } => match action {
-            JsonAction::Format { input, indent } => commands::json::format(&input, indent),
-            JsonAction::Minify { input } => commands::json::minify(&input),
-            JsonAction::Validate { input } => commands::json::validate(&input),
+            JsonAction::Format { input, indent } => {
+                commands::json::format(input.source(), indent).map(Output::from)
+            }
+            JsonAction::Minify { input } => commands::json::minify(input.source()),
+            JsonAction::Validate { input } => {
+                commands::json::validate(input.source()).map(Output::from)
+            }
+            JsonAction::Stats { input, top } => {
+                commands::json::stats(input.source(), top).map(Output::from)
+            }
+            JsonAction::ToCsv {
+                input,
+                separator,
+                arrays,
+            } => commands::csv::from_json(input.source(), &separator, &arrays).map(Output::from),
+            JsonAction::Canonicalize { input } => {
+                commands::json::canonicalize(input.source()).map(Output::from)
+            }
+            JsonAction::Get {
+                pointer,
+                input,
+                raw,
+            } => commands::json::get(input.source(), &pointer, raw).map(Output::from),
+            JsonAction::Set {
+                pointer,
+                value,
+                input,
+                string,
+                json,
+                parents,
+                in_place,
+            } => {
+                let value_type = if string {
+                    ValueType::String
+                } else if json {
+                    ValueType::Json
+                } else {
+                    ValueType::Auto
+                };
+                commands::json::set(
+                    input.source(),
+                    &pointer,
+                    &value,
+                    value_type,
+                    parents,
+                    in_place,
+                )
+                .map(Output::from)
+            }
+            JsonAction::Delete {
+                pointer,
+                input,
+                in_place,
+            } => commands::json::delete(input.source(), &pointer, in_place).map(Output::from),
+            JsonAction::Codegen { input, lang, name } => {
+                commands::json::codegen(input.source(), &lang, &name).map(Output::from)
+            }
         },
 
+        Commands::Convert { input, from, to } => {
+            commands::convert::convert(input.source(), from.as_deref(), &to).map(Output::from)
+        }
+
+        Commands::Csv { action } => match action {
+            CsvAction::ToJson {
+                input,
+                delimiter,
+                no_infer,
+            } => commands::csv::to_json(input.source(), delimiter, !no_infer),
+        }
+        .map(Output::from),
+
         Commands::Time { action } => match action {
-            TimeAction::Now => commands::time::now(),
-            TimeAction::FromUnix { timestamp } => commands::time::from_unix(timestamp),
-            TimeAction::ToUnix { date } => commands::time::to_unix(&date),
+            TimeAction::Now { display } => {
+                commands::time::now(display.show_tz(), display.format()).map(Output::from)
+            }
+            TimeAction::FromUnix {
+                timestamp,
+                unit,
+                display,
+            } => commands::time::from_unix(
+                timestamp,
+                unit.as_deref(),
+                display.show_tz(),
+                display.format(),
+            )
+            .map(Output::from),
+            TimeAction::FromId { id, kind, display } => {
+                commands::time::from_id(&id, kind.as_deref(), display.show_tz(), display.format())
+                    .map(Output::from)
+            }
+            TimeAction::ToUnix {
+                date,
+                zone,
+                display,
+            } => commands::time::to_unix(&date, zone.tz(), display.show_tz(), display.format())
+                .map(Output::from),
+            TimeAction::Add {
+                date,
+                duration,
+                zone,
+                display,
+            } => commands::time::add(
+                &date,
+                &duration,
+                zone.tz(),
+                display.show_tz(),
+                display.format(),
+            )
+            .map(Output::from),
+            TimeAction::Diff { from, to, zone } => {
+                commands::time::diff(&from, &to, zone.tz()).map(Output::from)
+            }
+            TimeAction::Duration { duration } => {
+                commands::time::duration(&duration).map(Output::from)
+            }
+            TimeAction::Info { date, tz } => {
+                commands::time::info(&date, tz.as_deref()).map(Output::from)
+            }
+            TimeAction::Cal { month, tz } => {
+                commands::time::cal(month.as_deref(), tz.as_deref()).map(Output::from)
+            }
+            TimeAction::Annotate {
+                path,
+                tz,
+                replace,
+                output,
+            } => commands::time::annotate(&path, tz.as_deref(), replace, output.format.as_deref()),
+            TimeAction::Cron {
+                expression,
+                next,
+                tz,
+                output,
+            } => commands::time::cron(&expression, next, tz.as_deref(), output.format.as_deref())
+                .map(Output::from),
         },
 
+        Commands::Escape { input, target } => {
+            commands::escape::escape(input.source(), &target).map(Output::from)
+        }
+        Commands::Unescape { input, target } => {
+            commands::escape::unescape(input.source(), &target).map(Output::from)
+        }
+
         Commands::Url { action } => match action {
-            UrlAction::Encode { input } => commands::url::encode(&input),
-            UrlAction::Decode { input } => commands::url::decode(&input),
+            UrlAction::Encode { input, component } => commands::url::Component::parse(&component)
+                .and_then(|component| commands::url::encode(&input, component))
+                .map(Output::from),
+            UrlAction::Decode {
+                input,
+                form,
+                strict,
+                bytes,
+            } => {
+                if bytes {
+                    commands::url::decode_bytes(&input, form, strict)
+                } else {
+                    commands::url::decode(&input, form, strict).map(Output::from)
+                }
+            }
+            UrlAction::Build {
+                base,
+                segments,
+                params,
+                fragment,
+            } => commands::url::build(&base, &segments, &params, fragment.as_deref())
+                .map(Output::from),
+            UrlAction::SetQuery { url, key, value } => {
+                commands::url::set_query(&url, &key, &value).map(Output::from)
+            }
+            UrlAction::RemoveQuery { url, key } => {
+                commands::url::remove_query(&url, &key).map(Output::from)
+            }
+            UrlAction::QueryToJson { input } => commands::input::read_input(input.source())
+                .and_then(|s| commands::url::query_to_json(&s))
+                .map(Output::from),
+            UrlAction::JsonToQuery { input, arrays } => {
+                commands::url::json_to_query(input.source(), &arrays).map(Output::from)
+            }
+            UrlAction::Normalize { input, sort_query } => {
+                commands::input::read_input(input.source())
+                    .and_then(|s| commands::url::normalize(&s, sort_query))
+                    .map(Output::from)
+            }
+            UrlAction::Resolve { base, reference } => {
+                commands::url::resolve(&base, &reference).map(Output::from)
+            }
+            UrlAction::Idn { action } => match action {
+                IdnAction::ToAscii { domain } => commands::url::idn_to_ascii(&domain),
+                IdnAction::ToUnicode { domain } => commands::url::idn_to_unicode(&domain),
+            }
+            .map(Output::from),
+            UrlAction::Parse { input, json } => commands::input::read_input(input.source())
+                .and_then(|s| commands::url::parse(&s, json))
+                .map(Output::from),
         },
     };
 
     match result {
-        Ok(output) => println!("{}", output),
+        Ok(Output::Text(output)) => println!("{}", output),
+        Ok(Output::Written) => {}
         Err(e) => {
             eprintln!("{} {}", "Error:".red().bold(), e);
-            std::process::exit(1);
+            let code = e
+                .downcast_ref::<commands::ExitError>()
+                .map_or(1, |exit| exit.code);
+            std::process::exit(code);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_set_negative_value() {
+        let cli = Cli::try_parse_from(["devkit", "json", "set", "/x", "-5", "{}"]).unwrap();
+        let Commands::Json {
+            action: JsonAction::Set { value, input, .. },
+        } = cli.command
+        else {
+            panic!("expected json set");
+        };
+        assert_eq!(value, "-5");
+        assert_eq!(input.input, "{}");
+
+        // Known flags still take precedence
+        let cli = Cli::try_parse_from(["devkit", "json", "set", "/x", "-p", "5", "{}"]).unwrap();
+        let Commands::Json {
+            action: JsonAction::Set { value, parents, .. },
+        } = cli.command
+        else {
+            panic!("expected json set");
+        };
+        assert_eq!(value, "5");
+        assert!(parents);
+    }
+
+    #[test]
+    fn test_time_display_args() {
+        let cli = Cli::try_parse_from([
+            "devkit", "time", "add", "now", "1d", "--tz", "UTC", "--format", "unix",
+        ])
+        .unwrap();
+        let Commands::Time {
+            action: TimeAction::Add { zone, display, .. },
+        } = cli.command
+        else {
+            panic!("expected time add");
+        };
+        assert_eq!(zone.tz(), Some("UTC"));
+        assert_eq!(display.format(), Some("unix"));
+        assert_eq!(display.show_tz(), None);
+
+        assert!(Cli::try_parse_from([
+            "devkit",
+            "time",
+            "now",
+            "--format",
+            "unix",
+            "--show-tz",
+            "UTC"
+        ])
+        .is_err());
+        // Commands without --show-tz still take --format
+        assert!(
+            Cli::try_parse_from(["devkit", "time", "cron", "@daily", "--format", "unix"]).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_url_encode_takes_literal_text() {
+        // `@` is not file syntax here: scoped package names start with it
+        let cli = Cli::try_parse_from(["devkit", "url", "encode", "@babel/core"]).unwrap();
+        let Commands::Url {
+            action: UrlAction::Encode { input, .. },
+        } = cli.command
+        else {
+            panic!("expected url encode");
+        };
+        assert_eq!(input, "@babel/core");
+    }
+}